@@ -1,4 +1,6 @@
 use std::env;
+use std::fs;
+use std::path::PathBuf;
 
 fn main() {
     let target = env::var("TARGET").unwrap();
@@ -48,4 +50,16 @@ fn main() {
     if target.ends_with("-eabihf") {
         println!("cargo:rustc-cfg=has_fpu");
     }
+
+    // Memory regions included by link.ld
+    let memory = if env::var_os("CARGO_FEATURE_MPS2_AN385").is_some() || env::var_os("CARGO_FEATURE_MPS2_AN386").is_some() {
+        "memory_mps2_an385.x"
+    } else {
+        "memory_stm32.x"
+    };
+
+    let out = PathBuf::from(env::var("OUT_DIR").unwrap());
+    fs::copy(memory, out.join("memory.x")).unwrap();
+    println!("cargo:rustc-link-search={}", out.display());
+    println!("cargo:rerun-if-changed={}", memory);
 }
\ No newline at end of file
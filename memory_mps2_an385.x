@@ -0,0 +1,10 @@
+/* ARM MPS2 AN385 (Cortex-M3), as emulated by qemu-system-arm -machine mps2-an385 */
+MEMORY
+{
+    /* NOTE 1 K = 1 KiBi = 1024 bytes */
+    /* ZBT SSRAM1 is code memory: QEMU loads the ELF there and boots from its vector table */
+    FLASH   (rx)    : ORIGIN = 0x00000000,   LENGTH = 4M
+    /* ZBT SSRAM2 and SSRAM3 */
+    RAM     (xrw)   : ORIGIN = 0x20000000,   LENGTH = 4M - 16K
+    STACK   (xrw)   : ORIGIN = 0x203FC000,   LENGTH = 16K
+}
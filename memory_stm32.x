@@ -0,0 +1,9 @@
+/* Memoria del chip STM32F334R8T6, usato per lo sviluppo */
+MEMORY
+{
+    /* NOTE 1 K = 1 KiBi = 1024 bytes */
+    FLASH   (rx)    : ORIGIN = 0x08000000,   LENGTH = 64K
+    CCMRAM  (xrw)   : ORIGIN = 0x10000000,   LENGTH = 4K
+    RAM     (xrw)   : ORIGIN = 0x20000000,   LENGTH = 10K
+    STACK   (xrw)   : ORIGIN = 0x20003000,   LENGTH = 2K
+}
@@ -13,8 +13,8 @@
 //! You should have received a copy of the GNU General Public License
 //! along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-#![no_std]
-#![no_main]
+#![cfg_attr(not(native), no_std)]
+#![cfg_attr(not(native), no_main)]
 
 use RusTOS::drivers::serial::SerialPort;
 use RusTOS::drivers::serial::SerialStream;
@@ -45,6 +45,9 @@ pub extern "C" fn OSEntry() -> ! {
     let k = KERNEL.access(&cs);
     let _ = k.add_task(&CIAO_TASK);
     let _ = k.add_task(&BELLO_TASK);
+    // QEMU boards print the demo data on the console
+    #[cfg(any(feature = "qemu_riscv_virt", feature = "mps2"))]
+    let _ = k.add_task(&UART_TASK);
     
     #[cfg(feature = "timers")]
     let _ = k.new_timer(
@@ -59,6 +62,12 @@ pub extern "C" fn OSEntry() -> ! {
     k.init(cs);
 }
 
+/// Host simulation port: OSEntry is called like on a reset
+#[cfg(native)]
+fn main() {
+    OSEntry();
+}
+
 fn blink_led() {
     LED.with(|_, led| { let _ = led.toggle(); } );
 }
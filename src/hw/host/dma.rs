@@ -0,0 +1,23 @@
+//! RusTOS - Rust Real Time Operating System 
+//! Copyright (C) 2025 - Fabio Zanin - fabio.zanin93@outlook.com
+//! 
+//! This program is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License.
+//! 
+//! This program is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+//! 
+//! You should have received a copy of the GNU General Public License
+//! along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// The host has no DMA controller: word sizes are still needed by DMA buffers and peripherals traits.
+
+#[derive(Debug, Clone, Copy)]
+pub enum DmaWordSize {
+    Byte,
+    HalfWord,
+    Word,
+}
@@ -0,0 +1,157 @@
+//! RusTOS - Rust Real Time Operating System 
+//! Copyright (C) 2025 - Fabio Zanin - fabio.zanin93@outlook.com
+//! 
+//! This program is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License.
+//! 
+//! This program is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+//! 
+//! You should have received a copy of the GNU General Public License
+//! along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//! 
+//! Host simulation board: the pins of ports A, B and C are kept in memory, with the same numbering
+//! of STM32 ports, so that examples written for STM32 run unchanged on the host.
+//! Input pins read the values written with set_input_level, i.e. by a test.
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::hal::gpio::*;
+
+impl PinSpeed {
+    fn code(self) -> usize {
+        match self {
+            PinSpeed::Slow => 0b00,
+            PinSpeed::Medium => 0b01,
+            PinSpeed::Fast => 0b10,
+            PinSpeed::VeryFast => 0b11,
+        }
+    }
+}
+
+/// Port registers live in PORTS: there is nothing mapped at the port address
+pub(crate) struct Gpio<const ADR: usize>;
+
+/// Output data, input data and mode of a simulated port
+struct PortState {
+    od: AtomicUsize,
+    id: AtomicUsize,
+    mode: AtomicUsize,
+}
+
+impl PortState {
+    const fn new() -> Self {
+        Self {
+            od: AtomicUsize::new(0),
+            id: AtomicUsize::new(0),
+            mode: AtomicUsize::new(0),
+        }
+    }
+}
+
+static PORTS: [PortState; 3] = [const { PortState::new() }; 3];
+
+/// Drives an input pin of the simulated board: port 0 is A, 1 is B, 2 is C
+pub fn set_input_level(port: usize, n: usize, high: bool) {
+    if high {
+        PORTS[port].id.fetch_or(1 << n, Ordering::Relaxed);
+    } else {
+        PORTS[port].id.fetch_and(!(1 << n), Ordering::Relaxed);
+    }
+}
+
+macro_rules! create_gpio {
+    ($Port:ident, $idx:expr) => {
+        impl GpioPort for $Port {
+            #[inline]
+            fn init_port(self) {
+                // No clock to activate
+            }
+
+            #[inline]
+            fn set_high(self, n: usize) {
+                PORTS[$idx].od.fetch_or(1 << n, Ordering::Relaxed);
+            }
+        
+            #[inline]
+            fn is_set_high(self, n: usize) -> bool {
+                PORTS[$idx].od.load(Ordering::Relaxed) & (1 << n) != 0
+            }
+        
+            #[inline]
+            fn is_high(self, n: usize) -> bool {
+                PORTS[$idx].id.load(Ordering::Relaxed) & (1 << n) != 0
+            }
+        
+            #[inline]
+            fn set_low(self, n: usize) {
+                PORTS[$idx].od.fetch_and(!(1 << n), Ordering::Relaxed);
+            }
+        
+            #[inline]
+            fn is_set_low(self, n: usize) -> bool {
+                !self.is_set_high(n)
+            }
+        
+            #[inline]
+            fn is_low(self, n: usize) -> bool {
+                !self.is_high(n)
+            }
+        
+            #[inline]
+            fn set_input(self, n: usize) {
+                PORTS[$idx].mode.fetch_and(!(0b11 << (n + n)), Ordering::Relaxed);
+            }
+        
+            #[inline]
+            fn set_nopull(self, _n: usize) {
+
+            }
+            
+            #[inline]
+            fn set_pullup(self, _n: usize) {
+
+            }
+        
+            #[inline]
+            fn set_pulldown(self, _n: usize) {
+
+            }
+        
+            #[inline]
+            fn set_out_pushpull(self, n: usize) {
+                self.set_input(n);
+                PORTS[$idx].mode.fetch_or(0b01 << (n + n), Ordering::Relaxed);
+            }
+        
+            #[inline]
+            fn set_out_opendrain(self, n: usize) {
+                self.set_out_pushpull(n);
+            }
+        
+            #[inline]
+            fn set_speed(self, _speed: usize, _n: usize) {
+
+            }
+        
+            #[inline]
+            fn set_alternate(self, _alternate: usize, n: usize) {
+                self.set_input(n);
+                PORTS[$idx].mode.fetch_or(0b10 << (n + n), Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+use crate::make_port;
+use core::marker::PhantomData;
+make_port!(A: Gpio, 0 => [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15]);
+make_port!(B: Gpio, 0 => [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15]);
+make_port!(C: Gpio, 0 => [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15]);
+
+create_gpio!(PORTA, 0);
+create_gpio!(PORTB, 1);
+create_gpio!(PORTC, 2);
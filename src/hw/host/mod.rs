@@ -0,0 +1,22 @@
+//! RusTOS - Rust Real Time Operating System 
+//! Copyright (C) 2025 - Fabio Zanin - fabio.zanin93@outlook.com
+//! 
+//! This program is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License.
+//! 
+//! This program is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+//! 
+//! You should have received a copy of the GNU General Public License
+//! along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//! 
+//! Host simulation board, selected instead of the board features when RusTOS is built for the host:
+//! no MMIO is done, peripherals used by examples are simulated in memory and on the standard streams.
+
+pub mod gpio;
+pub mod uart;
+pub mod dma;
+pub mod tim;
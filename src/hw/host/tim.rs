@@ -0,0 +1,26 @@
+//! RusTOS - Rust Real Time Operating System 
+//! Copyright (C) 2025 - Fabio Zanin - fabio.zanin93@outlook.com
+//! 
+//! This program is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License.
+//! 
+//! This program is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+//! 
+//! You should have received a copy of the GNU General Public License
+//! along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// The host has no general purpose timers: SysTick is simulated by the kernel.
+// Modes are declared only to satisfy hal::tim traits.
+
+pub enum CompareMode {
+    /// Compare disable. Only timebase
+    Frozen,
+}
+
+pub enum ComplementaryMode {
+    Disable,
+}
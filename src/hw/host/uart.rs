@@ -0,0 +1,128 @@
+//! RusTOS - Rust Real Time Operating System 
+//! Copyright (C) 2025 - Fabio Zanin - fabio.zanin93@outlook.com
+//! 
+//! This program is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License.
+//! 
+//! This program is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+//! 
+//! You should have received a copy of the GNU General Public License
+//! along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//! 
+//! Host simulation board: UART1 is the standard output and input of the process.
+
+use std::io::{Read, Write};
+
+use crate::hal::uart::*;
+use crate::hal::dma::*;
+use crate::hal::gpio::*;
+
+/// Standard streams, called UART1 to run STM32 examples unchanged
+pub struct UART1;
+
+/// Nothing is mapped at the UART address: DMA addresses are only declared
+impl crate::kernel::registers::Peripheral for UART1 {
+    type Registers = ();
+    const ADR: usize = 0;
+}
+
+impl Uart for UART1 {
+    #[inline]
+    fn tx(&self, tx: u8) {
+        let mut out = std::io::stdout();
+        let _ = out.write_all(&[tx]);
+        if tx == b'\n' {
+            let _ = out.flush();
+        }
+    }
+
+    /// Waits for a character on the standard input, 0 once it is closed
+    #[inline]
+    fn rx(&self) -> u8 {
+        let mut rx = [0];
+        match std::io::stdin().read(&mut rx) {
+            Ok(1) => rx[0],
+            _ => 0,
+        }
+    }
+
+    #[inline]
+    fn rx_fifo_not_empty(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    fn tx_fifo_not_full(&self) -> bool {
+        true
+    }
+
+    #[inline]
+    fn init(_baud: usize, _mode: SerialMode, _proto: SerialProto) -> Self {
+        Self {}
+    }
+
+    #[inline]
+    fn activate(&mut self) -> &mut Self {
+        self
+    }
+
+    #[inline]
+    fn deactivate(&mut self) -> &mut Self {
+        self
+    }
+
+    #[inline]
+    fn set_baud(&mut self, _baud: usize) -> &mut Self {
+        self
+    }
+
+    #[inline]
+    fn set_mode(&mut self, _mode: SerialMode) -> &mut Self {
+        self
+    }
+
+    #[inline]
+    fn set_proto(&mut self, _proto: SerialProto) -> &mut Self {
+        self
+    }
+
+    #[inline]
+    fn set_msb(&mut self, _typ: TRxType) -> &mut Self {
+        self
+    }
+
+    fn set_tx_pin(pin: &mut impl PinSetup) {
+        pin.init();
+    }
+
+    fn set_rx_pin(pin: &mut impl PinSetup) {
+        pin.init();
+    }
+}
+
+/// Pins are the STM32 ones, so that examples run unchanged
+impl UART1 {
+    pub const TX1: PA9<Alternate<AF7>> = PA9::allocate();
+    pub const RX1: PA10<Alternate<AF7>> = PA10::allocate();
+    pub const TX3: PC4<Alternate<AF7>> = PC4::allocate();
+    pub const RX3: PC5<Alternate<AF7>> = PC5::allocate();
+}
+
+/// No DMA on the host: triggers are software ones
+impl DmaPeripheralSource for UART1 {
+    const SRC: DmaAddress = DmaAddress::new(0);
+    const MODE: DmaMode = DmaMode::RepeatedWord;
+    const TRG: DmaTrigger = DmaTrigger::SW;
+    const WORD: DmaWordSize = DmaWordSize::Byte;
+}
+
+impl DmaPeripheralSink for UART1 {
+    const DST: DmaAddress = DmaAddress::new(0);
+    const MODE: DmaMode = DmaMode::RepeatedWord;
+    const TRG: DmaTrigger = DmaTrigger::SW;
+    const WORD: DmaWordSize = DmaWordSize::Byte;
+}
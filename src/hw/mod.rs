@@ -14,13 +14,33 @@
 //! along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 
-#[cfg(feature = "stm32")]
+#[cfg(all(feature = "stm32", not(native)))]
 mod stm32;
-#[cfg(feature = "g431")]
+#[cfg(all(feature = "g431", not(native)))]
 pub(crate) use stm32::g431::*;
 
 
-#[cfg(feature = "mspm0")]
+#[cfg(all(feature = "mspm0", not(native)))]
 mod ti_mspm0;
-#[cfg(feature = "m0g3507")]
+#[cfg(all(feature = "m0g3507", not(native)))]
 pub(crate) use ti_mspm0::g3507::*;
+
+
+#[cfg(all(feature = "mps2", not(native)))]
+mod mps2;
+/// AN386 (Cortex-M4) has the same memory map and peripherals of AN385
+#[cfg(all(any(feature = "mps2_an385", feature = "mps2_an386"), not(native)))]
+pub(crate) use mps2::an385::*;
+
+
+#[cfg(all(feature = "qemu_riscv_virt", not(native)))]
+mod qemu_riscv_virt;
+#[cfg(all(feature = "qemu_riscv_virt", not(native)))]
+pub(crate) use qemu_riscv_virt::*;
+
+
+/// Host simulation: board features are ignored, no MMIO can be done
+#[cfg(native)]
+mod host;
+#[cfg(native)]
+pub(crate) use host::*;
@@ -0,0 +1,145 @@
+//! RusTOS - Rust Real Time Operating System 
+//! Copyright (C) 2025 - Fabio Zanin - fabio.zanin93@outlook.com
+//! 
+//! This program is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License.
+//! 
+//! This program is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+//! 
+//! You should have received a copy of the GNU General Public License
+//! along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//! 
+//! CMSDK AHB GPIO: 16 pins per port, no pull resistors, no speed settings and no open-drain outputs.
+//! Each pin has a single alternate function, selected by ALTFUNC whatever is the AF number.
+//! QEMU does not emulate these ports: writes are ignored and reads return 0.
+
+use crate::kernel::registers::*;
+use crate::hal::gpio::*;
+
+/// Pins speed is not configurable
+impl PinSpeed {
+    fn code(self) -> usize {
+        0
+    }
+}
+
+const GPIO0_ADR: usize = 0x4001_0000;
+const GPIO1_ADR: usize = 0x4001_1000;
+const GPIO2_ADR: usize = 0x4001_2000;
+const GPIO3_ADR: usize = 0x4001_3000;
+
+pub(crate) struct Gpio<const ADR: usize> {
+    data: RW<ADR, 0x000>,
+    dataout: RW<ADR, 0x004>,
+    outenset: RW<ADR, 0x010>,
+    outenclr: RW<ADR, 0x014>,
+    altfuncset: RW<ADR, 0x018>,
+    altfuncclr: RW<ADR, 0x01C>,
+    intenset: RW<ADR, 0x020>,
+    intenclr: RW<ADR, 0x024>,
+    inttypeset: RW<ADR, 0x028>,
+    inttypeclr: RW<ADR, 0x02C>,
+    intpolset: RW<ADR, 0x030>,
+    intpolclr: RW<ADR, 0x034>,
+    intstatus: RW<ADR, 0x038>,
+}
+
+macro_rules! create_gpio {
+    ($Port:ident) => {
+        impl GpioPort for $Port {
+            #[inline]
+            fn init_port(self) {
+                // No clock to activate
+            }
+
+            #[inline]
+            fn set_high(self, n: usize) {
+                $Port::regs().dataout.set_bit(n);
+            }
+        
+            #[inline]
+            fn is_set_high(self, n: usize) -> bool {
+                $Port::regs().dataout.read_bit(n)
+            }
+        
+            #[inline]
+            fn is_high(self, n: usize) -> bool {
+                $Port::regs().data.read_bit(n)
+            }
+        
+            #[inline]
+            fn set_low(self, n: usize) {
+                $Port::regs().dataout.clear_bit(n);
+            }
+        
+            #[inline]
+            fn is_set_low(self, n: usize) -> bool {
+                $Port::regs().dataout.read_bit(n) == false
+            }
+        
+            #[inline]
+            fn is_low(self, n: usize) -> bool {
+                $Port::regs().data.read_bit(n) == false
+            }
+        
+            #[inline]
+            fn set_input(self, n: usize) {
+                $Port::regs().altfuncclr.write(1 << n);
+                $Port::regs().outenclr.write(1 << n);
+            }
+        
+            #[inline]
+            fn set_nopull(self, _n: usize) {
+                // No pull resistors
+            }
+            
+            #[inline]
+            fn set_pullup(self, _n: usize) {
+                // No pull resistors
+            }
+        
+            #[inline]
+            fn set_pulldown(self, _n: usize) {
+                // No pull resistors
+            }
+        
+            #[inline]
+            fn set_out_pushpull(self, n: usize) {
+                $Port::regs().altfuncclr.write(1 << n);
+                $Port::regs().outenset.write(1 << n);
+            }
+        
+            #[inline]
+            fn set_out_opendrain(self, n: usize) {
+                // Only push-pull outputs
+                self.set_out_pushpull(n);
+            }
+        
+            #[inline]
+            fn set_speed(self, _speed: usize, _n: usize) {
+                // No speed settings
+            }
+        
+            #[inline]
+            fn set_alternate(self, _alternate: usize, n: usize) {
+                $Port::regs().altfuncset.write(1 << n);
+            }
+        }
+    }
+}
+
+use crate::make_port;
+use core::marker::PhantomData;
+make_port!(A: Gpio, GPIO0_ADR => [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15]);
+make_port!(B: Gpio, GPIO1_ADR => [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15]);
+make_port!(C: Gpio, GPIO2_ADR => [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15]);
+make_port!(D: Gpio, GPIO3_ADR => [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15]);
+
+create_gpio!(PORTA);
+create_gpio!(PORTB);
+create_gpio!(PORTC);
+create_gpio!(PORTD);
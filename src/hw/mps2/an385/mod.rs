@@ -0,0 +1,225 @@
+//! RusTOS - Rust Real Time Operating System 
+//! Copyright (C) 2025 - Fabio Zanin - fabio.zanin93@outlook.com
+//! 
+//! This program is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License.
+//! 
+//! This program is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+//! 
+//! You should have received a copy of the GNU General Public License
+//! along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//! 
+//! ARM MPS2 board with AN385 FPGA image (Cortex-M3), as emulated by qemu-system-arm -machine mps2-an385.
+//! AN386 FPGA image (Cortex-M4, -machine mps2-an386) shares the same memory map and peripherals.
+//! 
+//! Memory map, from ARM AN385 and QEMU hw/arm/mps2.c:
+//! 
+//! | Peripheral  | Address       |
+//! |-------------|---------------|
+//! | SSRAM1      | 0x0000_0000   |
+//! | SSRAM2/3    | 0x2000_0000   |
+//! | TIMER0      | 0x4000_0000   |
+//! | TIMER1      | 0x4000_1000   |
+//! | DUALTIMER   | 0x4000_2000   |
+//! | UART0       | 0x4000_4000   |
+//! | UART1       | 0x4000_5000   |
+//! | UART2       | 0x4000_6000   |
+//! | UART3       | 0x4000_7000   |
+//! | UART4       | 0x4000_9000   |
+//! | GPIO0       | 0x4001_0000   |
+//! | GPIO1       | 0x4001_1000   |
+//! | GPIO2       | 0x4001_2000   |
+//! | GPIO3       | 0x4001_3000   |
+//! 
+//! Peripherals are the CMSDK ones, clocked by the 25MHz system clock.
+
+pub mod gpio;
+pub mod uart;
+pub mod dma;
+pub mod tim;
+
+use crate::kernel::{Kernel, MHz, Vector};
+
+pub(crate) const CPU_FREQUENCY: MHz = MHz::new(25);
+
+#[no_mangle]
+#[cfg(armv7m)]
+#[link_section = ".vector_table_interrupts"]
+static __INTERRUPTS: [Vector; 32] = [
+    Vector { handler: UARTRX0_ISR },
+    Vector { handler: UARTTX0_ISR },
+    Vector { handler: UARTRX1_ISR },
+    Vector { handler: UARTTX1_ISR },
+    Vector { handler: UARTRX2_ISR },
+    Vector { handler: UARTTX2_ISR },
+    Vector { handler: PORT0_ALL_ISR },
+    Vector { handler: PORT1_ALL_ISR },
+    Vector { handler: TIMER0_ISR },
+    Vector { handler: TIMER1_ISR },
+    Vector { handler: DUALTIMER_ISR },
+    Vector { handler: SPI_ISR },
+    Vector { handler: UARTOVF_ISR },
+    Vector { handler: ETHERNET_ISR },
+    Vector { handler: I2S_ISR },
+    Vector { handler: TSC_ISR },
+    Vector { handler: PORT2_ALL_ISR },
+    Vector { handler: PORT3_ALL_ISR },
+    Vector { handler: UARTRX3_ISR },
+    Vector { handler: UARTTX3_ISR },
+    Vector { handler: UARTRX4_ISR },
+    Vector { handler: UARTTX4_ISR },
+    Vector { handler: ADCSPI_ISR },
+    Vector { handler: SHIELDSPI_ISR },
+    Vector { handler: PORT0_0_ISR },
+    Vector { handler: PORT0_1_ISR },
+    Vector { handler: PORT0_2_ISR },
+    Vector { handler: PORT0_3_ISR },
+    Vector { handler: PORT0_4_ISR },
+    Vector { handler: PORT0_5_ISR },
+    Vector { handler: PORT0_6_ISR },
+    Vector { handler: PORT0_7_ISR },
+];
+
+impl Kernel {
+    /// Nothing to do: system clock is fixed by the FPGA image
+    #[inline]
+    pub(crate) fn setup_clock(&self) {
+
+    }
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn UARTRX0_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn UARTTX0_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn UARTRX1_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn UARTTX1_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn UARTRX2_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn UARTTX2_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn PORT0_ALL_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn PORT1_ALL_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn TIMER0_ISR() {
+    tim::TIMER0::on_interrupt();
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn TIMER1_ISR() {
+    tim::TIMER1::on_interrupt();
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn DUALTIMER_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn SPI_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn UARTOVF_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn ETHERNET_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn I2S_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn TSC_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn PORT2_ALL_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn PORT3_ALL_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn UARTRX3_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn UARTTX3_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn UARTRX4_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn UARTTX4_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn ADCSPI_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn SHIELDSPI_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn PORT0_0_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn PORT0_1_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn PORT0_2_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn PORT0_3_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn PORT0_4_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn PORT0_5_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn PORT0_6_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn PORT0_7_ISR() {}
@@ -0,0 +1,150 @@
+//! RusTOS - Rust Real Time Operating System 
+//! Copyright (C) 2025 - Fabio Zanin - fabio.zanin93@outlook.com
+//! 
+//! This program is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License.
+//! 
+//! This program is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+//! 
+//! You should have received a copy of the GNU General Public License
+//! along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::kernel::registers::*;
+use crate::hal::tim::*;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use super::CPU_FREQUENCY;
+
+
+//*********************************************************************************************************************
+// HW-CONNECTED VARIABLES
+//*********************************************************************************************************************
+
+//********************* ADDRESSES *************************
+const TIMER0_ADR: usize = 0x4000_0000;
+const TIMER1_ADR: usize = 0x4000_1000;
+
+//********************* BIT MASKS *************************
+const CTRL_EN: usize = 1;
+const CTRL_EXTIN_EN: usize = 1 << 1;
+const CTRL_EXTIN_CLK: usize = 1 << 2;
+const CTRL_IRQ_EN: usize = 1 << 3;
+
+const INTSTATUS_INT: usize = 1;
+
+
+//*********************************************************************************************************************
+// MACRO FOR TIMER CREATION
+//*********************************************************************************************************************
+macro_rules! make_timebase {
+    ($TIM:ident: $regs:ident, $addr:expr, $bits:ty, $dir:expr, $one_shot:ident) => {
+        pub struct $TIM;
+
+        /// CMSDK timer has no one shot mode: it is emulated by the timer ISR
+        static $one_shot: AtomicBool = AtomicBool::new(false);
+
+        impl $TIM {
+            /// To be called by the timer ISR: clears the interrupt and stops the counter in OneShot mode,
+            /// that has reloaded in the meantime, so that next start runs a whole period again
+            #[inline]
+            pub(crate) fn on_interrupt() {
+                $TIM::regs().intstatus.write(INTSTATUS_INT);
+                if $one_shot.load(Ordering::Relaxed) {
+                    $TIM::regs().ctrl.clear(CTRL_EN);
+                }
+            }
+        }
+
+        impl crate::kernel::registers::Peripheral for $TIM {
+            type Registers = $regs<$addr>;
+            const ADR: usize = $addr;
+        }
+
+        impl TimeBase for $TIM {
+            type BITS = $bits;
+            const DIR: CountDir = $dir;
+
+            #[inline]
+            fn init_clock(&self) {
+                // Always clocked by PCLK
+            }
+
+            #[inline]
+            fn start(&mut self) {
+                $TIM::regs().ctrl.set(CTRL_EN);
+            }
+
+            #[inline]
+            fn stop(&mut self) {
+                $TIM::regs().ctrl.clear(CTRL_EN);
+            }
+
+            /// CMSDK timer always reloads when reaching 0: OneShot stops it in the ISR, so the interrupt must be active
+            #[inline]
+            fn set_mode(&mut self, mode: TimMode) {
+                $one_shot.store(matches!(mode, TimMode::OneShot), Ordering::Relaxed);
+            }
+
+            /// There is no prescaler: counter runs at PCLK and reloads when reaching 0.
+            /// Frequencies above PCLK are clamped to PCLK
+            #[inline]
+            fn set_frequency(&mut self, freq: Hz) {
+                let pclk: Hz = CPU_FREQUENCY.into();
+                let reload: usize = (pclk / freq).into();
+                let reload = reload.max(1);
+
+                $TIM::regs().ctrl.clear(CTRL_EXTIN_EN | CTRL_EXTIN_CLK);
+                $TIM::regs().reload.write(reload - 1);
+                $TIM::regs().value.write(reload - 1);
+            }
+
+            #[inline]
+            fn activate_interrupt(&mut self) {
+                $TIM::regs().intstatus.write(INTSTATUS_INT);
+                $TIM::regs().ctrl.set(CTRL_IRQ_EN);
+            }
+
+            #[inline]
+            fn deactivate_interrupt(&mut self) {
+                $TIM::regs().intstatus.write(INTSTATUS_INT);
+                $TIM::regs().ctrl.clear(CTRL_IRQ_EN);
+            }
+        }
+    }
+}
+
+//*********************************************************************************************************************
+// TIMER ADDITIONAL FUNCTIONALITIES
+//*********************************************************************************************************************
+
+// CMSDK timers have no compare channels: modes are declared only to satisfy hal::tim traits.
+
+#[derive(Debug, Clone, Copy)]
+pub enum CompareMode {
+    /// Compare disable. Only timebase
+    Frozen,
+}
+
+pub enum ComplementaryMode {
+    Disable,
+}
+
+//*********************************************************************************************************************
+// TIMER DECLARATION
+//*********************************************************************************************************************
+
+/// CMSDK APB timer: 32 bits down counter, to be used for timebase purposes or to fire IRQs.
+pub(crate) struct CmsdkTimer<const ADR: usize> {
+    ctrl: RW<ADR, 0x00>,
+    value: RW<ADR, 0x04>,
+    reload: RW<ADR, 0x08>,
+    /// Write 1 to clear
+    intstatus: RW<ADR, 0x0C>,
+}
+
+make_timebase!(TIMER0: CmsdkTimer, TIMER0_ADR, u32, CountDir::Down, TIMER0_ONE_SHOT);
+make_timebase!(TIMER1: CmsdkTimer, TIMER1_ADR, u32, CountDir::Down, TIMER1_ONE_SHOT);
@@ -0,0 +1,239 @@
+//! RusTOS - Rust Real Time Operating System 
+//! Copyright (C) 2025 - Fabio Zanin - fabio.zanin93@outlook.com
+//! 
+//! This program is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License.
+//! 
+//! This program is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+//! 
+//! You should have received a copy of the GNU General Public License
+//! along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::kernel::registers::*;
+use crate::hal::uart::*;
+use crate::hal::dma::*;
+use crate::hal::gpio::*;
+
+use super::CPU_FREQUENCY;
+
+
+//*********************************************************************************************************************
+// HW-CONNECTED VARIABLES
+//*********************************************************************************************************************
+
+//********************* ADDRESSES *************************
+const UART0_ADR: usize = 0x4000_4000;
+const UART1_ADR: usize = 0x4000_5000;
+const UART2_ADR: usize = 0x4000_6000;
+const UART3_ADR: usize = 0x4000_7000;
+const UART4_ADR: usize = 0x4000_9000;
+
+//********************* BIT MASKS *************************
+const STATE_TXBF: usize = 1;
+const STATE_RXBF: usize = 1 << 1;
+const STATE_TXOR: usize = 1 << 2;
+const STATE_RXOR: usize = 1 << 3;
+
+const CTRL_TXEN: usize = 1;
+const CTRL_RXEN: usize = 1 << 1;
+const CTRL_TXIE: usize = 1 << 2;
+const CTRL_RXIE: usize = 1 << 3;
+const CTRL_TXOIE: usize = 1 << 4;
+const CTRL_RXOIE: usize = 1 << 5;
+
+/// Minimum divider accepted by CMSDK UART
+const BAUDDIV_MIN: usize = 16;
+
+
+//*********************************************************************************************************************
+// UART DECLARATION
+//*********************************************************************************************************************
+
+/// CMSDK APB UART: 8N1 frames only, with a single byte buffer for each direction
+pub(crate) struct UART<const ADR: usize> {
+    data: RW<ADR, 0x00>,
+    state: RW<ADR, 0x04>,
+    ctrl: RW<ADR, 0x08>,
+    intstatus: RW<ADR, 0x0C>,
+    bauddiv: RW<ADR, 0x10>,
+}
+
+impl<const ADR: usize> UART<ADR> {
+    const DATA: usize = ADR;
+
+    #[inline]
+    fn init_hw(&mut self) {
+        self.state.write(STATE_TXOR | STATE_RXOR);
+        self.ctrl.set(CTRL_TXEN | CTRL_RXEN);
+    }
+
+    #[inline]
+    fn deinit_hw(&mut self) {
+        self.ctrl.clear(CTRL_TXEN | CTRL_RXEN | CTRL_TXIE | CTRL_RXIE | CTRL_TXOIE | CTRL_RXOIE);
+    }
+
+    /// Waits for transmit buffer to be empty
+    #[inline]
+    fn tx(&self, tx: u8) {
+        while !self.tx_fifo_not_full() {}
+        self.data.write(tx as usize);
+    }
+
+    /// Waits for a received character
+    #[inline]
+    fn rx(&self) -> u8 {
+        while !self.rx_fifo_not_empty() {}
+        self.data.read() as u8
+    } 
+
+    #[inline]
+    fn rx_fifo_not_empty(&self) -> bool {
+        self.state.check(STATE_RXBF)
+    }
+
+    #[inline]
+    fn tx_fifo_not_full(&self) -> bool {
+        !self.state.check(STATE_TXBF)
+    }
+
+    #[inline]
+    fn set_baud(&mut self, baud: usize) {
+        let clock: crate::kernel::Hz = CPU_FREQUENCY.into();
+        let clock: usize = clock.into();
+        self.bauddiv.write((clock / baud).max(BAUDDIV_MIN));
+    }
+
+    /// CMSDK UART has only 8 bits frames: other modes are ignored and frames stay 8N1
+    #[inline]
+    fn set_mode(&mut self, _mode: SerialMode) {}
+
+    /// CMSDK UART has no driver enable output, nor LIN support: the line is always driven as RS232
+    #[inline]
+    fn set_proto(&mut self, _proto: SerialProto) {}
+
+    /// CMSDK UART always transmits LSB first: MsbFirst is ignored
+    #[inline]
+    fn set_msb(&mut self, _typ: TRxType) {}
+}
+
+
+macro_rules! make_uarts {
+    ($peripheral:ident: $regs:ident, $addr:expr) => {
+        pub struct $peripheral;
+
+        impl crate::kernel::registers::Peripheral for $peripheral {
+            type Registers = $regs<$addr>;
+            const ADR: usize = $addr;
+        }
+
+        impl Uart for $peripheral {
+            #[inline]
+            fn tx(&self, tx: u8) {
+                $peripheral::regs().tx(tx);
+            }
+
+            #[inline]
+            fn rx(&self) -> u8 {
+                $peripheral::regs().rx()
+            }
+
+            #[inline]
+            fn rx_fifo_not_empty(&self) -> bool {
+                $peripheral::regs().rx_fifo_not_empty()
+            }
+
+            #[inline]
+            fn tx_fifo_not_full(&self) -> bool {
+                $peripheral::regs().tx_fifo_not_full()
+            }
+
+            #[inline]
+            fn init(baud: usize, mode: SerialMode, proto: SerialProto) -> Self {
+                let mut uart = Self {};
+                uart.set_baud(baud);
+                uart.set_mode(mode);
+                uart.set_proto(proto);
+                uart.activate();
+                uart
+            }
+
+            #[inline]
+            fn activate(&mut self) -> &mut Self {
+                $peripheral::regs().init_hw();
+                self
+            }
+
+            #[inline]
+            fn deactivate(&mut self) -> &mut Self {
+                $peripheral::regs().deinit_hw();
+                self
+            }
+        
+            #[inline]
+            fn set_baud(&mut self, baud: usize) -> &mut Self {
+                $peripheral::regs().set_baud(baud);
+                self
+            }
+
+            #[inline]
+            fn set_mode(&mut self, mode: SerialMode) -> &mut Self {
+                $peripheral::regs().set_mode(mode);
+                self
+            }
+
+            #[inline]
+            fn set_proto(&mut self, proto: SerialProto) -> &mut Self {
+                $peripheral::regs().set_proto(proto);
+                self
+            }
+
+            #[inline]
+            fn set_msb(&mut self, typ: TRxType) -> &mut Self {
+                $peripheral::regs().set_msb(typ);
+                self
+            }
+
+            fn set_tx_pin(pin: &mut impl PinSetup) {
+                pin.init();
+            }
+
+            fn set_rx_pin(pin: &mut impl PinSetup) {
+                pin.init();
+            }
+        }
+
+        /// No DMA controller on AN385: triggers are software ones
+        impl DmaPeripheralSource for $peripheral {
+            const SRC: DmaAddress = DmaAddress::new(<Self as crate::kernel::registers::Peripheral>::Registers::DATA);
+            const MODE: DmaMode = DmaMode::RepeatedWord;
+            const TRG: DmaTrigger = DmaTrigger::SW;
+            const WORD: DmaWordSize = DmaWordSize::Byte;
+        }
+
+        impl DmaPeripheralSink for $peripheral {
+            const DST: DmaAddress = DmaAddress::new(<Self as crate::kernel::registers::Peripheral>::Registers::DATA);
+            const MODE: DmaMode = DmaMode::RepeatedWord;
+            const TRG: DmaTrigger = DmaTrigger::SW;
+            const WORD: DmaWordSize = DmaWordSize::Byte;
+        }
+    };
+}
+
+
+make_uarts!(UART0: UART, UART0_ADR);
+make_uarts!(UART1: UART, UART1_ADR);
+make_uarts!(UART2: UART, UART2_ADR);
+make_uarts!(UART3: UART, UART3_ADR);
+make_uarts!(UART4: UART, UART4_ADR);
+
+/// UART pins are not routed through GPIOs by QEMU: UART1 ones are the STM32 ones, so that examples run unchanged
+impl UART1 {
+    pub const TX1: PA9<Alternate<AF7>> = PA9::allocate();
+    pub const RX1: PA10<Alternate<AF7>> = PA10::allocate();
+    pub const TX3: PC4<Alternate<AF7>> = PC4::allocate();
+    pub const RX3: PC5<Alternate<AF7>> = PC5::allocate();
+}
@@ -0,0 +1,159 @@
+//! RusTOS - Rust Real Time Operating System 
+//! Copyright (C) 2025 - Fabio Zanin - fabio.zanin93@outlook.com
+//! 
+//! This program is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License.
+//! 
+//! This program is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+//! 
+//! You should have received a copy of the GNU General Public License
+//! along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//! 
+//! QEMU virt has no GPIOs: every port is a bank of plain RAM words, reserved by the linker script
+//! (GPIO memory region), with the same layout of STM32 ports. Writes have no side effects, but pins
+//! state can be watched from the QEMU monitor or GDB, and examples written for STM32 run unchanged.
+//! Input data registers are never written by the board: they can be written from the debugger.
+
+use crate::kernel::registers::*;
+use crate::hal::gpio::*;
+
+impl PinSpeed {
+    fn code(self) -> usize {
+        match self {
+            PinSpeed::Slow => 0b00,
+            PinSpeed::Medium => 0b01,
+            PinSpeed::Fast => 0b10,
+            PinSpeed::VeryFast => 0b11,
+        }
+    }
+}
+
+
+const GPIOA_ADR: usize = 0x8010_4000;
+const GPIOB_ADR: usize = 0x8010_4020;
+const GPIOC_ADR: usize = 0x8010_4040;
+
+pub(crate) struct Gpio<const ADR: usize> {
+    mode: RW<ADR, 0x00>,
+    otype: RW<ADR, 0x04>,
+    ospeed: RW<ADR, 0x08>,
+    pupd: RW<ADR, 0x0C>,
+    id: RO<ADR, 0x10>,
+    od: RW<ADR, 0x14>,
+    afl: RW<ADR, 0x18>,
+    afh: RW<ADR, 0x1C>,
+}
+
+macro_rules! create_gpio {
+    ($Port:ident) => {
+        impl GpioPort for $Port {
+            #[inline]
+            fn init_port(self) {
+                // No clock to activate
+            }
+
+            #[inline]
+            fn set_high(self, n: usize) {
+                $Port::regs().od.set_bit(n);
+            }
+        
+            #[inline]
+            fn is_set_high(self, n: usize) -> bool {
+                $Port::regs().od.read_bit(n)
+            }
+        
+            #[inline]
+            fn is_high(self, n: usize) -> bool {
+                $Port::regs().id.read_bit(n)
+            }
+        
+            #[inline]
+            fn set_low(self, n: usize) {
+                $Port::regs().od.clear_bit(n);
+            }
+        
+            #[inline]
+            fn is_set_low(self, n: usize) -> bool {
+                $Port::regs().od.read_bit(n) == false
+            }
+        
+            #[inline]
+            fn is_low(self, n: usize) -> bool {
+                $Port::regs().id.read_bit(n) == false
+            }
+        
+            #[inline]
+            fn set_input(self, n: usize) {
+                $Port::regs().mode.clear(0b11 << (n + n));
+            }
+        
+            #[inline]
+            fn set_nopull(self, n: usize) {
+                $Port::regs().pupd.clear(0b11 << (n + n));
+            }
+            
+            #[inline]
+            fn set_pullup(self, n: usize) {
+                $Port::regs().pupd.clear(0b11 << (n + n));
+                $Port::regs().pupd.set(0b01 << (n + n));
+            }
+        
+            #[inline]
+            fn set_pulldown(self, n: usize) {
+                $Port::regs().pupd.clear(0b11 << (n + n));
+                $Port::regs().pupd.set(0b10 << (n + n));
+            }
+        
+            #[inline]
+            fn set_out_pushpull(self, n: usize) {
+                $Port::regs().mode.clear(0b11 << (n + n));
+                $Port::regs().mode.set(0b01 << (n + n));
+                $Port::regs().otype.clear_bit(n);
+            }
+        
+            #[inline]
+            fn set_out_opendrain(self, n: usize) {
+                $Port::regs().mode.clear(0b11 << (n + n));
+                $Port::regs().mode.set(0b01 << (n + n));
+                $Port::regs().otype.set_bit(n);
+            }
+        
+            #[inline]
+            fn set_speed(self, speed: usize, n: usize) {
+                $Port::regs().ospeed.clear(0b11 << (n + n));
+                $Port::regs().ospeed.set(speed << (n + n));
+            }
+        
+            #[inline]
+            fn set_alternate(self, alternate: usize, n: usize) {
+                $Port::regs().mode.clear(0b11 << (n + n));
+                $Port::regs().mode.set(0b10 << (n + n));
+                match n {
+                    0..=7 => { 
+                        $Port::regs().afl.clear(0b1111 << (n << 2));
+                        $Port::regs().afl.set(alternate << (n << 2));
+                    },
+                    8..=15 => { 
+                        $Port::regs().afh.clear(0b1111 << ((n - 8) << 2));
+                        $Port::regs().afh.set(alternate << ((n - 8) << 2));
+                    },
+                    _ => (),
+                }
+            }
+        }
+    }
+}
+
+use crate::make_port;
+use core::marker::PhantomData;
+make_port!(A: Gpio, GPIOA_ADR => [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15]);
+make_port!(B: Gpio, GPIOB_ADR => [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15]);
+make_port!(C: Gpio, GPIOC_ADR => [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15]);
+
+create_gpio!(PORTA);
+create_gpio!(PORTB);
+create_gpio!(PORTC);
@@ -0,0 +1,159 @@
+//! RusTOS - Rust Real Time Operating System 
+//! Copyright (C) 2025 - Fabio Zanin - fabio.zanin93@outlook.com
+//! 
+//! This program is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License.
+//! 
+//! This program is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+//! 
+//! You should have received a copy of the GNU General Public License
+//! along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//! 
+//! QEMU "virt" RISC-V board (qemu-system-riscv32 -machine virt), single hart.
+//! 
+//! Memory map, from QEMU hw/riscv/virt.c:
+//! 
+//! | Peripheral  | Address       |
+//! |-------------|---------------|
+//! | CLINT       | 0x0200_0000   |
+//! | PLIC        | 0x0C00_0000   |
+//! | UART0       | 0x1000_0000   |
+//! | DRAM        | 0x8000_0000   |
+//! 
+//! The board has no GPIOs: ports are emulated by a register bank in RAM (see gpio.rs).
+
+pub mod plic;
+pub mod gpio;
+pub mod uart;
+pub mod dma;
+pub mod tim;
+
+use crate::kernel::{Kernel, MHz, Vector};
+
+use plic::*;
+
+pub(crate) const CPU_FREQUENCY: MHz = MHz::new(10);
+
+/// CLINT used by RISC-V kernel port for SysTick and context switches
+pub(crate) const CLINT_ADR: usize = 0x0200_0000;
+pub(crate) const MTIME_FREQUENCY: MHz = MHz::new(10);
+
+/// PLIC sources, indexed by interrupt ID (ID 0 means "no interrupt")
+#[no_mangle]
+static __INTERRUPTS: [Vector; 36] = [
+    Vector { reserved: 0 },
+    Vector { handler: VIRTIO0_ISR },
+    Vector { handler: VIRTIO1_ISR },
+    Vector { handler: VIRTIO2_ISR },
+    Vector { handler: VIRTIO3_ISR },
+    Vector { handler: VIRTIO4_ISR },
+    Vector { handler: VIRTIO5_ISR },
+    Vector { handler: VIRTIO6_ISR },
+    Vector { handler: VIRTIO7_ISR },
+    Vector { reserved: 0 },
+    Vector { handler: UART0_ISR },
+    Vector { handler: RTC_ISR },
+    Vector { reserved: 0 },
+    Vector { reserved: 0 },
+    Vector { reserved: 0 },
+    Vector { reserved: 0 },
+    Vector { reserved: 0 },
+    Vector { reserved: 0 },
+    Vector { reserved: 0 },
+    Vector { reserved: 0 },
+    Vector { reserved: 0 },
+    Vector { reserved: 0 },
+    Vector { reserved: 0 },
+    Vector { reserved: 0 },
+    Vector { reserved: 0 },
+    Vector { reserved: 0 },
+    Vector { reserved: 0 },
+    Vector { reserved: 0 },
+    Vector { reserved: 0 },
+    Vector { reserved: 0 },
+    Vector { reserved: 0 },
+    Vector { reserved: 0 },
+    Vector { handler: PCIE0_ISR },
+    Vector { handler: PCIE1_ISR },
+    Vector { handler: PCIE2_ISR },
+    Vector { handler: PCIE3_ISR },
+];
+
+/// Machine external interrupt: claims the source from PLIC and calls its handler
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn ExternalInterrupt() {
+    let id = PLIC::claim();
+    if id != 0 && id < __INTERRUPTS.len() {
+        unsafe { (__INTERRUPTS[id].handler)() };
+    }
+    PLIC::complete(id);
+}
+
+impl Kernel {
+    /// Nothing to do: QEMU has no clock tree
+    #[inline]
+    pub(crate) fn setup_clock(&self) {
+
+    }
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn VIRTIO0_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn VIRTIO1_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn VIRTIO2_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn VIRTIO3_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn VIRTIO4_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn VIRTIO5_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn VIRTIO6_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn VIRTIO7_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn UART0_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn RTC_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn PCIE0_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn PCIE1_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn PCIE2_ISR() {}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn PCIE3_ISR() {}
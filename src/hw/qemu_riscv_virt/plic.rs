@@ -0,0 +1,73 @@
+//! RusTOS - Rust Real Time Operating System 
+//! Copyright (C) 2025 - Fabio Zanin - fabio.zanin93@outlook.com
+//! 
+//! This program is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License.
+//! 
+//! This program is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+//! 
+//! You should have received a copy of the GNU General Public License
+//! along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::kernel::registers::*;
+
+const PLIC_ADR: usize = 0x0C00_0000;
+
+/// Hart 0 Machine mode is PLIC context 0
+pub(crate) struct Plic<const ADR: usize> {
+    /// Source priorities: zero means never interrupt
+    priority: RWArea<ADR, 0x00_0000, 64>,
+    pending: RWArea<ADR, 0x00_1000, 2>,
+    enable: RWArea<ADR, 0x00_2000, 2>,
+    threshold: RW<ADR, 0x20_0000>,
+    claim: RW<ADR, 0x20_0004>,
+}
+
+pub struct PLIC;
+
+impl Peripheral for PLIC {
+    type Registers = Plic<PLIC_ADR>;
+    const ADR: usize = PLIC_ADR;
+}
+
+impl PLIC {
+    pub const PRIO_MIN: usize = 1;
+    pub const PRIO_MAX: usize = 7;
+
+    #[inline]
+    pub fn enable_interrupt(id: usize, prio: usize) {
+        PLIC::regs().priority.write(id, prio);
+        PLIC::regs().enable.set_bit(id >> 5, id & 0x1F);
+    }
+
+    #[inline]
+    pub fn disable_interrupt(id: usize) {
+        PLIC::regs().enable.clear_bit(id >> 5, id & 0x1F);
+    }
+
+    #[inline]
+    pub fn is_pending(id: usize) -> bool {
+        PLIC::regs().pending.read_bit(id >> 5, id & 0x1F)
+    }
+
+    /// Sources with priority less or equal to threshold are masked
+    #[inline]
+    pub fn set_threshold(prio: usize) {
+        PLIC::regs().threshold.write(prio);
+    }
+
+    /// Highest priority pending source, zero if none
+    #[inline]
+    pub(crate) fn claim() -> usize {
+        PLIC::regs().claim.read()
+    }
+
+    #[inline]
+    pub(crate) fn complete(id: usize) {
+        PLIC::regs().claim.write(id);
+    }
+}
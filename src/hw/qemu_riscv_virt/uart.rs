@@ -0,0 +1,255 @@
+//! RusTOS - Rust Real Time Operating System 
+//! Copyright (C) 2025 - Fabio Zanin - fabio.zanin93@outlook.com
+//! 
+//! This program is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License.
+//! 
+//! This program is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+//! 
+//! You should have received a copy of the GNU General Public License
+//! along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::kernel::registers::*;
+use crate::hal::uart::*;
+use crate::hal::dma::*;
+use crate::hal::gpio::*;
+
+
+//*********************************************************************************************************************
+// HW-CONNECTED VARIABLES
+//*********************************************************************************************************************
+
+//********************* ADDRESSES *************************
+const UART0_ADR: usize = 0x1000_0000;
+
+/// NS16550A input clock, as declared by QEMU device tree
+const UART_CLOCK: usize = 3_686_400;
+
+//********************* BIT MASKS *************************
+const FCR_FIFOEN: u8 = 1;
+const FCR_RXRST: u8 = 1 << 1;
+const FCR_TXRST: u8 = 1 << 2;
+
+const LCR_WLS_MASK: u8 = 0b11;
+const LCR_WLS_7BITS: u8 = 0b10;
+const LCR_WLS_8BITS: u8 = 0b11;
+const LCR_STB: u8 = 1 << 2;
+const LCR_PEN: u8 = 1 << 3;
+const LCR_DLAB: u8 = 1 << 7;
+
+const MCR_DTR: u8 = 1;
+const MCR_RTS: u8 = 1 << 1;
+const MCR_OUT2: u8 = 1 << 3;
+
+const LSR_DR: u8 = 1;
+const LSR_THRE: u8 = 1 << 5;
+
+
+//*********************************************************************************************************************
+// UART DECLARATION
+//*********************************************************************************************************************
+
+/// NS16550A registers: RBR/THR and IER are Divisor Latch when LCR_DLAB is set
+pub(crate) struct NS16550<const ADR: usize> {
+    /// Receive buffer (read), transmit holding (write), divisor latch LSB
+    rbr_thr_dll: RW8<ADR, 0x00>,
+
+    /// Interrupt enable register, divisor latch MSB
+    ier_dlm: RW8<ADR, 0x01>,
+
+    /// FIFO control register (write only)
+    fcr: RW8<ADR, 0x02>,
+
+    /// Line control register
+    lcr: RW8<ADR, 0x03>,
+
+    /// Modem control register
+    mcr: RW8<ADR, 0x04>,
+
+    /// Line status register
+    lsr: RO8<ADR, 0x05>,
+}
+
+impl<const ADR: usize> NS16550<ADR> {
+    const RBR: usize = ADR;
+    const THR: usize = ADR;
+
+    #[inline]
+    fn init_hw(&mut self) {
+        self.ier_dlm.write(0);
+        self.fcr.write(FCR_FIFOEN | FCR_RXRST | FCR_TXRST);
+        self.mcr.write(MCR_DTR | MCR_RTS | MCR_OUT2);
+    }
+
+    #[inline]
+    fn deinit_hw(&mut self) {
+        self.ier_dlm.write(0);
+        self.fcr.write(0);
+        self.mcr.write(0);
+    }
+
+    /// Waits for transmit holding register to be empty
+    #[inline]
+    fn tx(&self, tx: u8) {
+        while !self.tx_fifo_not_full() {}
+        self.rbr_thr_dll.write(tx);
+    }
+
+    /// Waits for a received character
+    #[inline]
+    fn rx(&self) -> u8 {
+        while !self.rx_fifo_not_empty() {}
+        self.rbr_thr_dll.read()
+    } 
+
+    #[inline]
+    fn rx_fifo_not_empty(&self) -> bool {
+        self.lsr.check(LSR_DR)
+    }
+
+    #[inline]
+    fn tx_fifo_not_full(&self) -> bool {
+        self.lsr.check(LSR_THRE)
+    }
+
+    #[inline]
+    fn set_baud(&mut self, baud: usize) {
+        let divisor = UART_CLOCK / (16 * baud);
+        self.lcr.set(LCR_DLAB);
+        self.rbr_thr_dll.write(divisor as u8);
+        self.ier_dlm.write((divisor >> 8) as u8);
+        self.lcr.clear(LCR_DLAB);
+    }
+
+    /// 16550 has no 9 bits frames: Mode9N1 is ignored and the previous frame format is kept
+    #[inline]
+    fn set_mode(&mut self, mode: SerialMode) {
+        let wls = match mode {
+            SerialMode::Mode7N1 => LCR_WLS_7BITS,
+            SerialMode::Mode8N1 => LCR_WLS_8BITS,
+            SerialMode::Mode9N1 => return,
+        };
+        self.lcr.clear(LCR_WLS_MASK | LCR_STB | LCR_PEN);
+        self.lcr.set(wls);
+    }
+
+    /// 16550 has no driver enable output, nor LIN support: the line is always driven as RS232
+    #[inline]
+    fn set_proto(&mut self, _proto: SerialProto) {}
+
+    /// 16550 always transmits LSB first: MsbFirst is ignored
+    #[inline]
+    fn set_msb(&mut self, _typ: TRxType) {}
+}
+
+
+/// First serial port of QEMU virt ("-serial" option), called UART1 to run STM32 examples unchanged
+pub struct UART1;
+
+impl crate::kernel::registers::Peripheral for UART1 {
+    type Registers = NS16550<UART0_ADR>;
+    const ADR: usize = UART0_ADR;
+}
+
+impl Uart for UART1 {
+    #[inline]
+    fn tx(&self, tx: u8) {
+        UART1::regs().tx(tx);
+    }
+
+    #[inline]
+    fn rx(&self) -> u8 {
+        UART1::regs().rx()
+    }
+
+    #[inline]
+    fn rx_fifo_not_empty(&self) -> bool {
+        UART1::regs().rx_fifo_not_empty()
+    }
+
+    #[inline]
+    fn tx_fifo_not_full(&self) -> bool {
+        UART1::regs().tx_fifo_not_full()
+    }
+
+    #[inline]
+    fn init(baud: usize, mode: SerialMode, proto: SerialProto) -> Self {
+        let mut uart = Self {};
+        uart.set_baud(baud);
+        uart.set_mode(mode);
+        uart.set_proto(proto);
+        uart.activate();
+        uart
+    }
+
+    #[inline]
+    fn activate(&mut self) -> &mut Self {
+        UART1::regs().init_hw();
+        self
+    }
+
+    #[inline]
+    fn deactivate(&mut self) -> &mut Self {
+        UART1::regs().deinit_hw();
+        self
+    }
+
+    #[inline]
+    fn set_baud(&mut self, baud: usize) -> &mut Self {
+        UART1::regs().set_baud(baud);
+        self
+    }
+
+    #[inline]
+    fn set_mode(&mut self, mode: SerialMode) -> &mut Self {
+        UART1::regs().set_mode(mode);
+        self
+    }
+
+    #[inline]
+    fn set_proto(&mut self, proto: SerialProto) -> &mut Self {
+        UART1::regs().set_proto(proto);
+        self
+    }
+
+    #[inline]
+    fn set_msb(&mut self, typ: TRxType) -> &mut Self {
+        UART1::regs().set_msb(typ);
+        self
+    }
+
+    fn set_tx_pin(pin: &mut impl PinSetup) {
+        pin.init();
+    }
+
+    fn set_rx_pin(pin: &mut impl PinSetup) {
+        pin.init();
+    }
+}
+
+/// Pins are not routed by QEMU: they are the STM32 ones, so that examples run unchanged
+impl UART1 {
+    pub const TX1: PA9<Alternate<AF7>> = PA9::allocate();
+    pub const RX1: PA10<Alternate<AF7>> = PA10::allocate();
+    pub const TX3: PC4<Alternate<AF7>> = PC4::allocate();
+    pub const RX3: PC5<Alternate<AF7>> = PC5::allocate();
+}
+
+/// No DMA controller on QEMU virt: triggers are software ones
+impl DmaPeripheralSource for UART1 {
+    const SRC: DmaAddress = DmaAddress::new(Self::Registers::RBR);
+    const MODE: DmaMode = DmaMode::RepeatedWord;
+    const TRG: DmaTrigger = DmaTrigger::SW;
+    const WORD: DmaWordSize = DmaWordSize::Byte;
+}
+
+impl DmaPeripheralSink for UART1 {
+    const DST: DmaAddress = DmaAddress::new(Self::Registers::THR);
+    const MODE: DmaMode = DmaMode::RepeatedWord;
+    const TRG: DmaTrigger = DmaTrigger::SW;
+    const WORD: DmaWordSize = DmaWordSize::Byte;
+}
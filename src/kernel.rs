@@ -16,7 +16,7 @@
 //! ************************************************* KERNEL OF RusTOS ************************************************
 //! 
 //! This module is the heart of RusTOS, as it implements all foundamentals objects:
-//! Timers, Tasks, Kernel, Semaphores, Rendezvous, Mutexes, Queues and Stream Buffers.
+//! Timers, Tasks, Kernel, Semaphores, Rendezvous, Event Groups, Mutexes, Queues, Stream Buffers and Memory Pools.
 //! 
 //! Scheduling logic is implemented by Kernel struct with a couple of functions that interacts
 //! with an assembly code, specific for the CPU architecture which RusTOS will be run on.
@@ -37,20 +37,43 @@ use arch::core::FpuContext;
 
 #[cfg(feature = "mpu_enabled")]
 use arch::core::MpuContext;
+#[cfg(feature = "mpu_enabled")]
+pub use arch::core::{MpuAccess, MpuMemory, MpuError};
 
 use arch::core::ExceptionFrame;
+pub use arch::core::FaultRegisters;
 
 pub mod time;
 pub use time::*;
 pub(crate) mod registers;
 
+#[cfg(feature = "heap")]
+pub mod heap;
+
+pub mod crash;
+use crash::ResetReason;
+
+#[cfg(feature = "cpu_stats")]
+pub mod stats;
+#[cfg(feature = "cpu_stats")]
+use stats::{CpuStats, Stats};
+
+#[cfg(feature = "trace")]
+pub mod trace;
+#[cfg(feature = "trace")]
+use trace::TraceEvent;
+
 use crate::bitvec::AtomicBitVec;
 use crate::bitvec::BitVec;
 
 use core::cell::Cell;
 use core::cell::UnsafeCell;
+use core::fmt::{self, Display, Formatter, Write};
 use core::marker::PhantomData;
 use core::mem::MaybeUninit;
+use core::mem::size_of;
+use core::ops::{Deref, DerefMut};
+#[cfg(not(native))]
 use core::panic::PanicInfo;
 use core::ptr::NonNull;
 
@@ -171,11 +194,20 @@ impl<T: Sized + Default> CriticalCell<T> {
 }
 
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug)]
 pub(crate) struct NullablePtr<T: ?Sized> {
     ptr: Option<NonNull<T>>
 }
 
+/// Implemented by hand, as derive would require T to be Copy too
+impl<T: ?Sized> Clone for NullablePtr<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: ?Sized> Copy for NullablePtr<T> {}
+
 impl<T: ?Sized> NullablePtr<T> {
     pub const fn null() -> Self {
         Self {
@@ -254,7 +286,17 @@ impl<T: ?Sized> NullablePtr<T> {
 pub static KERNEL: CriticalCell<Kernel> = CriticalCell::new(Kernel::new());
 
 const IDLE_PRIO: usize = 255;
-static IDLE_STACK: Stack::<32> = Stack::new();
+/// Unused stack words are painted with this pattern, so that the high-water mark can be found
+const STACK_PAINT: usize = usize::from_ne_bytes([0xA5; size_of::<usize>()]);
+/// Lowest stack word: if it gets overwritten the Task has overflowed its stack
+const STACK_CANARY: usize = usize::from_ne_bytes([0x5A; size_of::<usize>()]);
+/// Idle stack holds the exception frame saved when idle is switched out, plus its own words
+const IDLE_FRAME_WORDS: usize = size_of::<ExceptionFrame>().div_ceil(size_of::<usize>()) + 4;
+#[cfg(not(feature = "tickless"))]
+static IDLE_STACK: Stack::<{ 32 + IDLE_FRAME_WORDS }> = Stack::new();
+/// Tickless idle computes the next wake-up on idle stack
+#[cfg(feature = "tickless")]
+static IDLE_STACK: Stack::<{ 128 + IDLE_FRAME_WORDS }> = Stack::new();
 pub static mut IDLE_TASK: Task = Task::new(idle_task, IDLE_PRIO, &IDLE_STACK);
 
 
@@ -264,60 +306,152 @@ pub static mut IDLE_TASK: Task = Task::new(idle_task, IDLE_PRIO, &IDLE_STACK);
 
 pub(crate) fn idle_task(_task: &mut Task) -> ! {
     loop {
+        #[cfg(not(feature = "tickless"))]
         Kernel::core_sleep();
+
+        #[cfg(feature = "tickless")]
+        Kernel::tickless_sleep();
     }
 }
 
 
+/// Panic message is printed on the console and kept in the crash log, then the system is reset
+#[cfg(not(native))]
 #[panic_handler]
-fn panic_handler(_: &PanicInfo) -> ! {
-    loop {}
+fn panic_handler(info: &PanicInfo) -> ! {
+    Kernel::interrupt_disable();
+    crash::save_message(format_args!("{}", info));
+    unsafe { KERNEL.access_unsafe() }.print(format_args!("{}\n", info));
+    crash::save_reset(ResetReason::Panic, Kernel::running_prio());
+    Kernel::system_reset();
+}
+
+/// Exception that has stopped the running Task
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FaultKind {
+    Hard,
+    Memory,
+    Bus,
+    Usage,
+    Secure,
+}
+
+/// What the Kernel does with a Task that has faulted
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FaultAction {
+    /// Task is stopped and its Mutexes are released
+    Stop,
+    /// Task starts again from its entry point, with a fresh stack
+    Restart,
+    /// Whole system is reset
+    Reset,
+}
+
+/// Fault decoded by the Kernel: given to the fault hook and printed on the console
+#[derive(Clone, Copy, Debug)]
+pub struct FaultReport {
+    pub kind: FaultKind,
+    /// Raised in handler mode, i.e. by an ISR or the Kernel: the running Task is not to blame
+    pub handler: bool,
+    /// Faulting Task: its slot, priority and entry point. IDLE_PRIO slot for the idle Task.
+    pub task: usize,
+    pub prio: usize,
+    pub entry: usize,
+    /// Program counter, link register and status register saved by the exception
+    pub pc: usize,
+    pub lr: usize,
+    pub status: usize,
+    /// Fault status registers of the core
+    pub regs: FaultRegisters,
+}
+
+impl FaultReport {
+    /// Fault status registers are read and cleared
+    fn new(kind: FaultKind, frame: &ExceptionFrame, task: &Task, handler: bool) -> Self {
+        Self {
+            kind,
+            handler,
+            task: task.id,
+            prio: task.prio,
+            entry: task.task as usize,
+            pc: frame.pc(),
+            lr: frame.lr(),
+            status: frame.status(),
+            regs: FaultRegisters::read(),
+        }
+    }
+}
+
+impl Display for FaultReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.handler {
+            writeln!(f, "{:?} fault in handler mode, running Task {} (prio {})", self.kind, self.task, self.prio)?;
+        } else {
+            writeln!(f, "{:?} fault in Task {} (prio {}, entry {:#010x})", self.kind, self.task, self.prio, self.entry)?;
+        }
+        writeln!(f, "PC={:#010x} LR={:#010x} PSR={:#010x}", self.pc, self.lr, self.status)?;
+        writeln!(f, "{}", self.regs)
+    }
+}
+
+/// Called with the report of every fault raised by a Task, but stack overflows
+pub type FaultHook = fn(&FaultReport) -> FaultAction;
+
+/// Writes Kernel messages (i.e. fault reports) on a console: must not block, it is called by fault handlers
+pub type ConsoleWrite = fn(&str);
+
+/// Formatter over a console
+struct Console(ConsoleWrite);
+
+impl Write for Console {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        (self.0)(s);
+        Ok(())
+    }
 }
 
 #[no_mangle]
 #[allow(non_snake_case)]
-fn OSHardFault(_frame: &ExceptionFrame, running: &mut Task) {
-    running.stop();
-
+fn OSHardFault(frame: &ExceptionFrame, _running: &mut Task, handler: bool) {
     let cs = CritSect::activate();
-    KERNEL.access(&cs).schedule_next();
+    KERNEL.access(&cs).fault(FaultKind::Hard, frame, handler);
     cs.deactivate();
 }
 
 #[no_mangle]
 #[cfg(not(armv6m))]
 #[allow(non_snake_case)]
-fn OSMemoryFault(_frame: &ExceptionFrame, _running: &mut Task) {
-    loop {
-
-    }
+fn OSMemoryFault(frame: &ExceptionFrame, _running: &mut Task, handler: bool) {
+    let cs = CritSect::activate();
+    KERNEL.access(&cs).fault(FaultKind::Memory, frame, handler);
+    cs.deactivate();
 }
 
 #[no_mangle]
 #[cfg(not(armv6m))]
 #[allow(non_snake_case)]
-fn OSBusFault(_frame: &ExceptionFrame, _running: &mut Task) {
-    loop {
-
-    }
+fn OSBusFault(frame: &ExceptionFrame, _running: &mut Task, handler: bool) {
+    let cs = CritSect::activate();
+    KERNEL.access(&cs).fault(FaultKind::Bus, frame, handler);
+    cs.deactivate();
 }
 
 #[no_mangle]
 #[cfg(not(armv6m))]
 #[allow(non_snake_case)]
-fn OSUsageFault(_frame: &ExceptionFrame, _running: &mut Task) {
-    loop {
-
-    }
+fn OSUsageFault(frame: &ExceptionFrame, _running: &mut Task, handler: bool) {
+    let cs = CritSect::activate();
+    KERNEL.access(&cs).fault(FaultKind::Usage, frame, handler);
+    cs.deactivate();
 }
 
 #[no_mangle]
 #[cfg(armv8m)]
 #[allow(non_snake_case)]
-fn OSSecureFault(_frame: &ExceptionFrame, running: &mut Task) {
-    loop {
-
-    }
+fn OSSecureFault(frame: &ExceptionFrame, _running: &mut Task, handler: bool) {
+    let cs = CritSect::activate();
+    KERNEL.access(&cs).fault(FaultKind::Secure, frame, handler);
+    cs.deactivate();
 }
 
 //*********************************************************************************************************************
@@ -355,17 +489,37 @@ impl<const WORDS: usize> Stack<WORDS> {
 pub struct Task {
     stack: *const [usize],
     stack_start: usize,
-    stack_watermark: usize,
 
     task: TaskFn,
     prio: usize,
+    /// Slot in the Tasks list, given when the Task is added to the Kernel
+    id: usize,
     semaphore: NullablePtr<Semaphore>,
+    /// Rendezvous the task is waiting at
+    rendezvous: NullablePtr<Rendezvous>,
+
+    /// Mutex the task is waiting for
+    mutex: NullablePtr<MutexLock>,
+    /// First of the Mutexes owned by the task, linked by MutexLock::next
+    owned: NullablePtr<MutexLock>,
+
+    /// EventGroup the task is waiting for
+    events: NullablePtr<EventGroup>,
+    /// Flags the task is waiting for, then flags that woke it up
+    event_flags: BitVec,
+    event_wait: EventWait,
+    event_clear: bool,
+
+    /// Notification value and pending state, see notify
+    notification: usize,
+    notified: bool,
 
     /// Forcing compiler to RAM-allocate this structure due to Cell presence
     ram_allocation: Cell<PhantomData<*const usize>>,
 
     context: CpuContext,
 
+    #[cfg(has_fpu)]
     #[cfg(feature = "fpu_enabled")]
     fpu: FpuContext,
 
@@ -373,6 +527,26 @@ pub struct Task {
     mpu: MpuContext,
 }
 
+/// Stack usage of a Task, in words
+#[derive(Clone, Copy, Debug)]
+pub struct StackUsage {
+    pub size: usize,
+    pub used: usize,
+    pub peak: usize,
+}
+
+/// What the Kernel does with a Task that has overflowed its stack
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OverflowAction {
+    /// Task is stopped and its Mutexes are released
+    Stop,
+    /// Task starts again from its entry point, with a fresh stack
+    Restart,
+}
+
+/// Called on context switch with the Task that has overflowed its stack
+pub type StackOverflowHook = fn(&Task) -> OverflowAction;
+
 /// Here as all is static and no one should modify ''stack: *const [usize]'' field
 unsafe impl Sync for Task {}
 
@@ -385,24 +559,59 @@ impl Task {
         Self {
             stack: stack.as_slice(),
             stack_start: 0,
-            stack_watermark: 0,
 
             task,
             prio,
+            id: IDLE_PRIO,
             semaphore: NullablePtr::null(),
+            rendezvous: NullablePtr::null(),
+            mutex: NullablePtr::null(),
+            owned: NullablePtr::null(),
+
+            events: NullablePtr::null(),
+            event_flags: BitVec::new(),
+            event_wait: EventWait::Any,
+            event_clear: false,
+
+            notification: 0,
+            notified: false,
             
             ram_allocation: Cell::new(PhantomData),
             
             context: CpuContext::new(),
-            //fpu: FpuContext::new(),
-            //mpu: MpuContext::new(),
+            #[cfg(has_fpu)]
+            #[cfg(feature = "fpu_enabled")]
+            fpu: FpuContext::new(),
+            #[cfg(feature = "mpu_enabled")]
+            mpu: MpuContext::new(),
         }
     }
 
-    pub(crate) fn update_watermark(&mut self) {
-        let words = (self.stack_start - self.context.sp()) >> 2;
-        if words > self.stack_watermark {
-            self.stack_watermark = words;
+    /// Paints the stack and the canary, then prepares the first context of the Task
+    pub(crate) fn init_stack(&mut self) {
+        let stack = unsafe { &mut *(self.stack as *mut [usize]) };
+        stack.fill(STACK_PAINT);
+        stack[0] = STACK_CANARY;
+
+        self.setup();
+    }
+
+    /// Canary overwritten, or stack pointer saved below the stack
+    pub(crate) fn stack_overflowed(&self) -> bool {
+        let stack = unsafe { &*self.stack };
+        stack[0] != STACK_CANARY || self.context.sp() < stack.as_ptr() as usize + size_of::<usize>()
+    }
+
+    /// Stack size, words in use at last context switch and high-water mark.
+    /// The high-water mark is found scanning the words still painted from the bottom of the stack.
+    pub fn stack_usage(&self) -> StackUsage {
+        let stack = unsafe { &*self.stack };
+        let unused = stack.iter().skip(1).take_while(|&&word| word == STACK_PAINT).count();
+
+        StackUsage {
+            size: stack.len(),
+            used: self.stack_start.saturating_sub(self.context.sp()) / size_of::<usize>(),
+            peak: stack.len() - 1 - unused,
         }
     }
 
@@ -414,26 +623,296 @@ impl Task {
         self.prio
     }
 
+    pub const fn id(&self) -> usize {
+        self.id
+    }
+
     pub fn idle(&mut self) {
-        SysCalls::set_task_idle(self.prio);
+        SysCalls::set_task_idle(self.id);
     }
 
     pub fn stop(&mut self) {
-        SysCalls::set_task_stop(self.prio);
+        SysCalls::set_task_stop(self.id);
     }
 
     pub fn sleep(&mut self, ticks: Ticks) {
-        SysCalls::set_task_sleep(self.prio, ticks);
+        let cs = CritSect::activate();
+        SysCalls::set_task_sleep(self.id, ticks, cs);
+    }
+
+    /// Sleeps till an absolute instant, so that periodic loops don't drift by their own execution time.
+    /// System ticks are read in the same critical section of the SysCall, so no tick can be lost in between.
+    /// If the instant has already passed the Task doesn't sleep, and the delay is returned as error.
+    /// Once slept, the Task may run after the instant as higher priority Tasks delay it: that is not an overrun.
+    pub fn sleep_until(&mut self, wake: Instant) -> Result<(), Duration> {
+        let mut slept = false;
+        loop {
+            let cs = CritSect::activate();
+            let now = KERNEL.access(&cs).ticks;
+            if wake.ticks() < now && !slept {
+                return Err(Instant::from_ticks(now) - wake);
+            }
+            if wake.ticks() <= now {
+                return Ok(());
+            }
+
+            // Sleeps longer than Ticks range are split
+            let ticks = (wake.ticks() - now).min(Ticks::MAX as SystemTicks) as Ticks;
+            SysCalls::set_task_sleep(self.id, ticks, cs);
+            slept = true;
+        }
+    }
+
+    /// Waits for a notification, then returns its value and clears the bits in clear_mask.
+    /// A zero timeout waits forever.
+    pub fn wait_notification(&mut self, clear_mask: usize, timeout: ms) -> Result<usize, NotifyError> {
+        let ticks: Ticks = timeout.into();
+        let mut cs = CritSect::activate();
+        if !KERNEL.access(&cs).tasks.get_ref(self.id).notified {
+            SysCalls::wait_notification(self.id, ticks, cs);
+            cs = CritSect::activate();
+        }
+
+        let task = KERNEL.access(&cs).tasks.get_ref_mut(self.id);
+        if task.notified {
+            let value = task.notification;
+            task.notification &= !clear_mask;
+            task.notified = false;
+            Ok(value)
+        } else {
+            Err(NotifyError::Timeout)
+        }
+    }
+}
+
+/// How notify updates the Task notification value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotifyAction {
+    /// Value is written only if the previous notification has been received
+    Set,
+    /// Value is written even if the previous notification is still pending
+    Overwrite,
+    /// Notification value is incremented by one, value is ignored
+    Increment,
+    /// Value bits are set in the notification value
+    SetBits,
+}
+
+/// Errors returned by notification APIs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotifyError {
+    /// NotifyAction::Set found the previous notification not received yet
+    Pending,
+    /// No notification has been received before timeout expiration
+    Timeout,
+}
+
+/// Sends a notification to a Task, waking it up if it is waiting for it.
+/// Fails only with NotifyAction::Set, if the Task has not received the previous notification.
+pub fn notify(task: &Task, value: usize, action: NotifyAction) -> Result<(), NotifyError> {
+    let cs = CritSect::activate();
+    if KERNEL.access(&cs).tasks.notify(task.id, value, action)? {
+        SysCalls::notify_task(task.id, cs);
+    }
+
+    Ok(())
+}
+
+/// Like notify, but without a SysCall: woken is set if the Task has to preempt the running one, see yield_from_isr
+pub fn notify_from_isr(task: &Task, value: usize, action: NotifyAction, woken: &mut bool) -> Result<(), NotifyError> {
+    let cs = CritSect::activate();
+    let k = KERNEL.access(&cs);
+    if k.tasks.notify(task.id, value, action)? {
+        k.tasks.idle(task.id);
+        *woken |= k.preempts(task.id);
+    }
+
+    Ok(())
+}
+
+/// Errors returned by suspend and resume APIs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SuspendError {
+    /// Task has not been added to the Kernel
+    NotAdded,
+    /// Task to be resumed is not suspended
+    NotSuspended,
+}
+
+/// Keeps a Task out of scheduling till it is resumed, the calling Task can suspend itself.
+/// A Task blocked on a Semaphore, a Rendezvous or any other object keeps waiting for it,
+/// and its timeout keeps running: if woken up meanwhile, it runs only once resumed.
+pub fn suspend(task: &Task) -> Result<(), SuspendError> {
+    if task.id == IDLE_PRIO {
+        return Err(SuspendError::NotAdded);
+    }
+
+    let cs = CritSect::activate();
+    let k = KERNEL.access(&cs);
+    // Before scheduler start, Tasks are scheduled by Kernel::init
+    if k.started() {
+        SysCalls::suspend_task(task.id, cs);
+    } else {
+        k.tasks.suspend(task.id);
+    }
+
+    Ok(())
+}
+
+/// Lets a suspended Task be scheduled again, preempting the calling one if it has an higher priority.
+/// Fails if the Task is not suspended.
+pub fn resume(task: &Task) -> Result<(), SuspendError> {
+    if task.id == IDLE_PRIO {
+        return Err(SuspendError::NotAdded);
+    }
+
+    let cs = CritSect::activate();
+    let k = KERNEL.access(&cs);
+    if !k.tasks.suspended.check(task.id) {
+        return Err(SuspendError::NotSuspended);
+    }
+
+    if k.started() {
+        SysCalls::resume_task(task.id, cs);
+    } else {
+        k.tasks.resume(task.id)?;
+    }
+
+    Ok(())
+}
+
+/// Like resume, but without a SysCall: woken is set if the Task has to preempt the running one, see yield_from_isr
+pub fn resume_from_isr(task: &Task, woken: &mut bool) -> Result<(), SuspendError> {
+    if task.id == IDLE_PRIO {
+        return Err(SuspendError::NotAdded);
+    }
+
+    let cs = CritSect::activate();
+    let k = KERNEL.access(&cs);
+    k.tasks.resume(task.id)?;
+    *woken |= k.preempts(task.id);
+
+    Ok(())
+}
+
+pub fn is_suspended(task: &Task) -> bool {
+    let cs = CritSect::activate();
+    task.id != IDLE_PRIO && KERNEL.access(&cs).tasks.suspended.check(task.id)
+}
+
+/// Ends an ISR that has called *_from_isr functions. They never make SysCalls nor call the scheduler:
+/// they only tell, through woken, if a Task with an higher priority than the running one has been woken up.
+/// In that case the context switch is requested here, once, and it takes place as soon as the ISR returns.
+pub fn yield_from_isr(woken: bool) {
+    if woken {
+        let cs = CritSect::activate();
+        KERNEL.access(&cs).schedule_next();
+    }
+}
+
+
+/// Errors returned by TaskPool APIs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PoolError {
+    /// All the pool slots are taken
+    Full,
+    /// Kernel has no free slot for another Task
+    KernelFull,
+    /// Task has not been spawned by this pool, or has already been deleted
+    NotInPool,
+}
+
+/// Tasks created and deleted at runtime: each of the N slots has its own TCB and stack.
+/// Slots are reclaimed by the Kernel when a Task is deleted, even if it deletes itself.
+pub struct TaskPool<const N: usize, const WORDS: usize> {
+    tasks: [UnsafeCell<MaybeUninit<Task>>; N],
+    stacks: [Stack<WORDS>; N],
+    used: AtomicBitVec,
+}
+
+unsafe impl<const N: usize, const WORDS: usize> Sync for TaskPool<N, WORDS> {}
+
+impl<const N: usize, const WORDS: usize> TaskPool<N, WORDS> {
+    pub const fn new() -> Self {
+        if N > BitVec::BITS {
+            panic!("Too many Tasks in pool!");
+        }
+
+        Self {
+            tasks: [const { UnsafeCell::new(MaybeUninit::uninit()) }; N],
+            stacks: [const { Stack::new() }; N],
+            used: AtomicBitVec::new(),
+        }
+    }
+
+    /// Creates a Task in a free slot and adds it to the Kernel. If the scheduler is running and
+    /// the new Task has an higher priority, it preempts the calling one.
+    pub fn spawn(&'static self, task: TaskFn, prio: usize) -> Result<&'static Task, PoolError> {
+        let cs = CritSect::activate();
+        let slot = match self.used.raw().trailing_ones() as usize {
+            slot if slot < N => slot,
+            _ => return Err(PoolError::Full),
+        };
+
+        let tcb = unsafe { (*self.tasks[slot].get()).write(Task::new(task, prio, &self.stacks[slot])) };
+        tcb.init_stack();
+
+        let k = KERNEL.access(&cs);
+        k.add_task(tcb).map_err(|_| PoolError::KernelFull)?;
+        self.used.set(slot);
+
+        // Before scheduler start, Tasks are scheduled by Kernel::init
+        if k.started() {
+            cs.deactivate();
+            SysCalls::set_task_idle(tcb.id);
+        }
+
+        Ok(tcb)
+    }
+
+    /// Removes the Task from the Kernel and gives its slot back to the pool.
+    /// Mutexes owned by the Task are handed over to their waiters.
+    pub fn delete(&'static self, task: &'static Task) -> Result<(), PoolError> {
+        let cs = CritSect::activate();
+        let slot = self.tasks.iter().position(|tcb| core::ptr::eq(tcb.get() as *const Task, task)).ok_or(PoolError::NotInPool)?;
+        if !self.used.check(slot) {
+            return Err(PoolError::NotInPool);
+        }
+
+        SysCalls::delete_task(task, &self.used, slot, cs);
+        Ok(())
+    }
+}
+
+impl<const N: usize, const WORDS: usize> Default for TaskPool<N, WORDS> {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 
+/// Tasks are stored in slots, so that more Tasks can share the same priority.
+/// Bit vectors are indexed by slot, while priority levels are handled with a bit vector of levels:
+/// a CLZ gives the highest level with ready Tasks, then Tasks of that level are served round-robin.
 struct TaskList {
     list: [MaybeUninit<*const Task>; BitVec::BITS],
     sleep_time: [Ticks; BitVec::BITS],
     used: BitVec,
     ready: BitVec,
     sleeping: BitVec,
+
+    /// Priority used for scheduling: a Task has its own priority, but it can inherit an higher one from Mutexes
+    effective: [usize; BitVec::BITS],
+    /// Tasks of each priority level, by effective priority
+    levels: [BitVec; BitVec::BITS],
+    /// Priority levels with at least one ready Task
+    ready_levels: BitVec,
+    /// Last Task scheduled for each priority level
+    last: [usize; BitVec::BITS],
+    /// Tasks waiting for a notification
+    notifying: BitVec,
+    /// Tasks kept out of scheduling: they keep waiting for objects, but run only once resumed
+    suspended: BitVec,
 }
 
 impl TaskList {
@@ -444,147 +923,544 @@ impl TaskList {
             used: BitVec::new(),
             ready: BitVec::new(),
             sleeping: BitVec::new(),
+            effective: [0; BitVec::BITS],
+            levels: [BitVec::new(); BitVec::BITS],
+            ready_levels: BitVec::new(),
+            last: [BitVec::HIGHEST_BIT; BitVec::BITS],
+            notifying: BitVec::new(),
+            suspended: BitVec::new(),
         }
     }
 
     const fn add_task(&mut self, task: &Task) -> Result<(), ()> {
-        if self.used.check(task.prio) == true {
+        let Ok(id) = self.used.find_first_zero() else {
+            return Err(());
+        };
+
+        if task.id != IDLE_PRIO {
             return Err(());
         }
 
-        self.list[task.prio] = MaybeUninit::new(task);
-        self.used.set(task.prio);
-        self.ready.set(task.prio);
+        // Slot is written in the Task like the Kernel does with all Tasks fields
+        unsafe { (*(task as *const Task as *mut Task)).id = id };
+
+        self.list[id] = MaybeUninit::new(task);
+        self.used.set(id);
+        self.effective[id] = task.prio;
+        self.levels[task.prio].set(id);
+        self.idle(id);
 
         Ok(())
     }
 
     const fn remove_task(&mut self, task: &Task) -> Result<(), ()> {
-        if self.used.check(task.prio) == false {
+        let id = task.id;
+        if id == IDLE_PRIO || self.used.check(id) == false {
             return Err(());
         }
 
-        self.list[task.prio] = MaybeUninit::zeroed();
-        self.used.clear(task.prio);
-        self.ready.clear(task.prio);
-        self.sleeping.clear(task.prio);
+        self.stop(id);
+        self.levels[self.effective[id]].clear(id);
+        self.list[id] = MaybeUninit::zeroed();
+        self.used.clear(id);
+        self.notifying.clear(id);
+        self.suspended.clear(id);
+        unsafe { (*(task as *const Task as *mut Task)).id = IDLE_PRIO };
 
         Ok(())
     }
 
     #[inline]
-    const fn get_ref_mut(&mut self, prio: usize) -> &mut Task {
-        unsafe { &mut *(self.list[prio].assume_init_read() as *mut Task) }
+    const fn get_ref_mut(&mut self, id: usize) -> &mut Task {
+        unsafe { &mut *(self.list[id].assume_init_read() as *mut Task) }
+    }
+
+    #[inline]
+    const fn get_ref(&self, id: usize) -> &Task {
+        unsafe { &*self.list[id].assume_init_read() }
+    }
+
+    /// Ready Task of the highest priority level. The last scheduled Task of the level keeps the CPU,
+    /// even if it has been preempted, unless it is running and its time slice is over.
+    fn next_waiting(&mut self, running: usize, rotate: bool) -> Result<usize, ()> {
+        let level = self.ready_levels.find_highest_set()?;
+        let ready = self.runnable() & self.levels[level];
+
+        let last = self.last[level];
+        if ready.check(last) && !(rotate && last == running) {
+            return Ok(last);
+        }
+
+        // Round-robin in slot order: first ready Task following the last scheduled one
+        let mut next = ready.find_first_set()?;
+        for id in &ready {
+            if id > last {
+                next = id;
+            }
+        }
+
+        self.last[level] = next;
+        Ok(next)
+    }
+
+    /// Ready Tasks that are not suspended
+    #[inline]
+    const fn runnable(&self) -> BitVec {
+        self.suspended.difference(&self.ready)
+    }
+
+    /// Keeps ready_levels in sync with runnable Tasks of the level
+    #[inline]
+    const fn update_level(&mut self, level: usize) {
+        if self.runnable().raw() & self.levels[level].raw() != 0 {
+            self.ready_levels.set(level);
+        } else {
+            self.ready_levels.clear(level);
+        }
+    }
+
+    /// Moves the Task to the level of its new effective priority
+    const fn set_effective(&mut self, id: usize, prio: usize) {
+        let old = self.effective[id];
+        self.levels[old].clear(id);
+        self.levels[prio].set(id);
+        self.effective[id] = prio;
+        self.update_level(old);
+        self.update_level(prio);
     }
 
     #[inline]
-    const fn get_ref(&self, prio: usize) -> &Task {
-        unsafe { &*self.list[prio].assume_init_read() }
+    const fn idle(&mut self, id: usize) {
+        self.ready.set(id);
+        self.sleeping.clear(id);
+        self.update_level(self.effective[id]);
     }
 
     #[inline]
-    const fn next_waiting(&self) -> Result<usize, ()>  {
-        self.ready.find_highest_set()
+    const fn stop(&mut self, id: usize) {
+        self.ready.clear(id);
+        self.sleeping.clear(id);
+        self.update_level(self.effective[id]);
     }
 
     #[inline]
-    const fn idle(&mut self, prio: usize) {
-        self.ready.set(prio);
-        self.sleeping.clear(prio);
+    const fn sleep(&mut self, id: usize, ticks: Ticks) {
+        self.sleep_time[id] = ticks;
+        self.ready.clear(id);
+        self.sleeping.set(id);
+        self.update_level(self.effective[id]);
     }
 
+    /// Task state is left as it is: a Task blocked on an object is still woken up by it,
+    /// but it is not scheduled till resume
     #[inline]
-    const fn stop(&mut self, prio: usize) {
-        self.ready.clear(prio);
-        self.sleeping.clear(prio);
+    const fn suspend(&mut self, id: usize) {
+        self.suspended.set(id);
+        self.update_level(self.effective[id]);
     }
 
+    /// Fails if the Task is not suspended
     #[inline]
-    const fn sleep(&mut self, prio: usize, ticks: Ticks) {
-        self.sleep_time[prio] = ticks;
-        self.ready.clear(prio);
-        self.sleeping.set(prio);
+    const fn resume(&mut self, id: usize) -> Result<(), SuspendError> {
+        if !self.suspended.check(id) {
+            return Err(SuspendError::NotSuspended);
+        }
+
+        self.suspended.clear(id);
+        self.update_level(self.effective[id]);
+        Ok(())
     }
 
     fn setup(&mut self) {
         // Setup of all inserted tasks
-        for prio in self.used.into_iter() {
-            self.get_ref_mut(prio).setup();
+        for id in self.used.into_iter() {
+            self.get_ref_mut(id).init_stack();
         }
     }
 
-    fn tick_sleeping(&mut self) {
-        // NOTE: could we use SIMD here?
-        for id in self.sleeping.into_iter() {
-            self.sleep_time[id] -= 1;
-            if self.sleep_time[id] == 0 {
-                self.ready.set(id);
-                self.sleeping.clear(id);
+    /// Highest effective priority Task in the vector
+    fn highest(&self, tasks: BitVec) -> Option<usize> {
+        let mut highest: Option<usize> = None;
 
-                if let Some(smph) = self.get_ref_mut(id).semaphore.take() {
-                    smph.locked.clear(id);
-                }
+        for id in &tasks {
+            match highest {
+                Some(prev) if self.effective[prev] >= self.effective[id] => {},
+                _ => highest = Some(id),
             }
         }
-    }
-}
 
+        highest
+    }
 
-//*********************************************************************************************************************
-// SOFTWARE TIMERS
-//*********************************************************************************************************************
+    /// Waiter an object is handed over to: suspended Tasks get it only if no other Task is waiting,
+    /// so that a release is not parked on a Task that can't run
+    fn next_waiter(&self, waiting: BitVec) -> Option<usize> {
+        self.highest(self.suspended.difference(&waiting)).or_else(|| self.highest(waiting))
+    }
 
-#[cfg(feature = "timers")]
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-pub enum TimerMode {
-    OneShot,
-    Looping,
-    #[cfg(feature = "timers_bursts")]
-    LoopingBurst(Ticks, u8),
-    Counted(u8),
-    #[cfg(feature = "timers_bursts")]
-    CountedBurst(u8, Ticks, u8),
-    Expired,
-}
+    /// Priority inheritance: owner of a Mutex runs at least at the priority of the Task waiting for it.
+    /// If the owner is waiting for another Mutex, the boost is passed to that Mutex owner too.
+    fn boost(&mut self, owner: usize, prio: usize) {
+        let mut owner = owner;
 
-#[cfg(feature = "timers")]
-pub struct Timer {
-    id: u8,
-    callback: fn(),
-    mode: TimerMode,
-    period: Duration,
-    cntdwn: Ticks,
-    #[cfg(feature = "timers_bursts")]
-    bursts_cnt: u8,
-}
+        while self.effective[owner] < prio {
+            self.set_effective(owner, prio);
 
-#[cfg(feature = "timers")]
-impl Timer {
-    const fn new(id: u8, period: Duration, callback: fn()) -> Self {
-        Self {
-            id,
-            callback,
-            mode : TimerMode::Expired,
-            period,
-            cntdwn: period.ticks(),
-            #[cfg(feature = "timers_bursts")]
-            bursts_cnt: 0,
+            match self.get_ref(owner).mutex.get().and_then(|mutex| mutex.owner()) {
+                Some(next) => owner = next,
+                None => break,
+            }
         }
     }
 
-    pub fn start(&self) {
-        let cs = CritSect::activate();
-        KERNEL.access(&cs).timers.active.set(self.id as usize);
-    }
+    /// Updates the Task notification value, returns true if the Task is waiting for it
+    fn notify(&mut self, id: usize, value: usize, action: NotifyAction) -> Result<bool, NotifyError> {
+        let task = self.get_ref_mut(id);
+        match action {
+            NotifyAction::Set if task.notified => return Err(NotifyError::Pending),
+            NotifyAction::Set | NotifyAction::Overwrite => task.notification = value,
+            NotifyAction::Increment => task.notification = task.notification.wrapping_add(1),
+            NotifyAction::SetBits => task.notification |= value,
+        }
+        task.notified = true;
 
-    pub fn stop(&self) {
-        let cs = CritSect::activate();
-        KERNEL.access(&cs).timers.active.clear(self.id as usize);
+        if self.notifying.check(id) {
+            self.notifying.clear(id);
+            Ok(true)
+        } else {
+            Ok(false)
+        }
     }
 
-    pub const fn set_mode(&mut self, mode: TimerMode) {
-        // Set mode and reloads timer countdown
-        self.mode = mode;
+    /// Wakes up the Task if flags satisfy its wait, storing matched flags in it.
+    /// Returns the flags to be cleared from the EventGroup.
+    fn wake_on_events(&mut self, id: usize, flags: BitVec) -> Option<BitVec> {
+        let task = self.get_ref_mut(id);
+        if !task.event_wait.satisfied(flags, task.event_flags) {
+            return None;
+        }
+
+        let matched = flags & task.event_flags;
+        let cleared = if task.event_clear { matched } else { BitVec::new() };
+        task.event_flags = matched;
+        self.idle(id);
+
+        Some(cleared)
+    }
+
+    /// A waiter gave up the Mutex: owners chain may have inherited its priority
+    fn unboost(&mut self, mutex: &MutexLock) {
+        let mut next = mutex.owner();
+
+        while let Some(owner) = next {
+            self.update_effective(owner);
+            next = self.get_ref(owner).mutex.get().and_then(|mutex| mutex.owner());
+        }
+    }
+
+    /// Recomputes the effective priority of a Task from the waiters of all the Mutexes it still owns
+    fn update_effective(&mut self, id: usize) {
+        let mut prio = self.get_ref(id).prio;
+        let mut owned = self.get_ref(id).owned;
+
+        while let Some(mutex) = owned.get() {
+            if let Some(waiter) = self.highest_waiter(mutex) {
+                if self.effective[waiter] > prio {
+                    prio = self.effective[waiter];
+                }
+            }
+            owned = mutex.next.get();
+        }
+
+        self.set_effective(id, prio);
+    }
+
+    fn highest_waiter(&self, mutex: &MutexLock) -> Option<usize> {
+        self.highest(mutex.waiting.raw().into())
+    }
+
+    /// Gives a free Mutex to the Task
+    fn take_mutex(&mut self, mutex: &MutexLock, id: usize) {
+        let task = self.get_ref_mut(id);
+        mutex.locker.set(NullablePtr::new(task));
+        mutex.next.set(task.owned);
+        task.owned = NullablePtr::new(mutex);
+    }
+
+    /// Mutex is handed over to the highest priority waiting Task, so that no lower priority Task
+    /// can take it in the meantime
+    fn release_mutex(&mut self, mutex: &MutexLock, id: usize) {
+        self.drop_mutex(mutex, id);
+        self.update_effective(id);
+
+        if let Some(next) = self.next_waiter(mutex.waiting.raw().into()) {
+            mutex.waiting.clear(next);
+            self.get_ref_mut(next).mutex.set(None);
+            self.take_mutex(mutex, next);
+            self.update_effective(next);
+            self.idle(next);
+        }
+    }
+
+    /// Removes the Task from all objects it is waiting for, and releases the Mutexes it owns
+    fn unlink(&mut self, id: usize) {
+        self.notifying.clear(id);
+
+        if let Some(smph) = self.get_ref_mut(id).semaphore.take() {
+            smph.locked.clear(id);
+        }
+
+        if let Some(rndv) = self.get_ref_mut(id).rendezvous.take() {
+            rndv.arrived.clear(id);
+        }
+
+        if let Some(events) = self.get_ref_mut(id).events.take() {
+            events.waiting.clear(id);
+        }
+
+        if let Some(mutex) = self.get_ref_mut(id).mutex.take() {
+            let mutex = mutex as *const MutexLock;
+            let mutex = unsafe { &*mutex };
+            mutex.waiting.clear(id);
+            self.unboost(mutex);
+        }
+
+        while let Some(mutex) = self.get_ref(id).owned.get() {
+            let mutex = mutex as *const MutexLock;
+            let mutex = unsafe { &*mutex };
+            mutex.count.set(0);
+            self.release_mutex(mutex, id);
+        }
+    }
+
+    /// Removes the Mutex from the ones owned by the Task
+    fn drop_mutex(&mut self, mutex: &MutexLock, id: usize) {
+        let task = self.get_ref_mut(id);
+        mutex.locker.set(NullablePtr::null());
+
+        if task.owned.get_ptr() == Some(mutex as *const MutexLock) {
+            task.owned = mutex.next.get();
+        } else {
+            let mut prev = task.owned;
+            while let Some(owned) = prev.get() {
+                if owned.next.get().get_ptr() == Some(mutex as *const MutexLock) {
+                    owned.next.set(mutex.next.get());
+                    break;
+                }
+                prev = owned.next.get();
+            }
+        }
+
+        mutex.next.set(NullablePtr::null());
+    }
+
+    /// Ticks till the first sleeping Task wakes up
+    #[cfg(feature = "tickless")]
+    fn next_wakeup(&self) -> Ticks {
+        let mut next = Ticks::MAX;
+        for id in self.sleeping.into_iter() {
+            if self.sleep_time[id] < next {
+                next = self.sleep_time[id];
+            }
+        }
+
+        next
+    }
+
+    /// Countdowns are not expected to expire here: ticks are less than next_wakeup()
+    #[cfg(feature = "tickless")]
+    fn skip_sleeping(&mut self, ticks: Ticks) {
+        for id in self.sleeping.into_iter() {
+            self.sleep_time[id] -= ticks;
+        }
+    }
+
+    fn tick_sleeping(&mut self) {
+        // NOTE: could we use SIMD here?
+        for id in self.sleeping.into_iter() {
+            self.sleep_time[id] -= 1;
+            if self.sleep_time[id] == 0 {
+                self.idle(id);
+
+                // Semaphore reference is kept to tell the waiting Task that the timeout expired, see wait_cs
+                if let Some(smph) = self.get_ref(id).semaphore.get() {
+                    smph.locked.clear(id);
+                    #[cfg(feature = "trace")]
+                    trace::record(TraceEvent::Unblock, id, 1, smph as *const Semaphore as usize);
+                }
+
+                self.notifying.clear(id);
+
+                if let Some(events) = self.get_ref_mut(id).events.take() {
+                    events.waiting.clear(id);
+                }
+
+                if let Some(mutex) = self.get_ref_mut(id).mutex.take() {
+                    let mutex = mutex as *const MutexLock;
+                    let mutex = unsafe { &*mutex };
+                    mutex.waiting.clear(id);
+                    self.unboost(mutex);
+                }
+            }
+        }
+    }
+}
+
+
+//*********************************************************************************************************************
+// SOFTWARE TIMERS
+//*********************************************************************************************************************
+
+#[cfg(feature = "timers")]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TimerMode {
+    OneShot,
+    Looping,
+    #[cfg(feature = "timers_bursts")]
+    LoopingBurst(Ticks, u8),
+    Counted(u8),
+    #[cfg(feature = "timers_bursts")]
+    CountedBurst(u8, Ticks, u8),
+    Expired,
+}
+
+/// Implemented by objects a Timer fires, like a closure together with its captured state
+#[cfg(feature = "timers")]
+pub trait TimerHandler: Sync {
+    fn on_timer(&self);
+}
+
+#[cfg(feature = "timers")]
+impl<F: Fn() + Sync> TimerHandler for F {
+    fn on_timer(&self) {
+        self()
+    }
+}
+
+/// What a Timer calls when it fires
+#[cfg(feature = "timers")]
+#[derive(Clone, Copy)]
+pub enum TimerCallback {
+    Fn(fn()),
+    /// Function with its context, see with_context
+    Context(fn(*const ()), *const ()),
+    Handler(&'static dyn TimerHandler),
+}
+
+#[cfg(feature = "timers")]
+impl TimerCallback {
+    /// Callback receiving a static context, i.e. the driver it has to poll
+    pub fn with_context<T: Sync>(callback: fn(&'static T), context: &'static T) -> Self {
+        // References and pointers to sized types are ABI compatible: the callback gets back its own context
+        let callback = unsafe { core::mem::transmute::<fn(&'static T), fn(*const ())>(callback) };
+        Self::Context(callback, context as *const T as *const ())
+    }
+
+    fn call(&self) {
+        match *self {
+            Self::Fn(callback) => callback(),
+            Self::Context(callback, context) => callback(context),
+            Self::Handler(handler) => handler.on_timer(),
+        }
+    }
+
+    /// Address of the called code, for the trace recorder
+    #[cfg(feature = "trace")]
+    fn addr(&self) -> usize {
+        match *self {
+            Self::Fn(callback) => callback as usize,
+            Self::Context(callback, _) => callback as usize,
+            Self::Handler(handler) => handler as *const dyn TimerHandler as *const () as usize,
+        }
+    }
+}
+
+#[cfg(feature = "timers")]
+pub struct Timer {
+    id: u8,
+    callback: TimerCallback,
+    mode: TimerMode,
+    period: Duration,
+    cntdwn: Ticks,
+    #[cfg(feature = "timers_bursts")]
+    bursts_cnt: u8,
+    /// Firings whose callback has still to be called
+    fired: u8,
+}
+
+#[cfg(feature = "timers")]
+impl Timer {
+    const fn new(id: u8, period: Duration, callback: TimerCallback) -> Self {
+        Self {
+            id,
+            callback,
+            mode : TimerMode::Expired,
+            period,
+            cntdwn: period.ticks(),
+            #[cfg(feature = "timers_bursts")]
+            bursts_cnt: 0,
+            fired: 0,
+        }
+    }
+
+    pub fn start(&self) {
+        let cs = CritSect::activate();
+        KERNEL.access(&cs).timers.active.set(self.id as usize);
+    }
+
+    pub fn stop(&self) {
+        let cs = CritSect::activate();
+        KERNEL.access(&cs).timers.active.clear(self.id as usize);
+    }
+
+    pub const fn id(&self) -> usize {
+        self.id as usize
+    }
+
+    /// Sends a command to the Timer service Task, waiting for room in the command queue up to timeout.
+    /// Fails if there is no Timer service Task or the queue stays full.
+    #[cfg(feature = "timer_service")]
+    pub fn command(&self, task: &mut Task, command: TimerCommand, timeout: ms) -> Result<(), TimerError> {
+        let cs = CritSect::activate();
+        if KERNEL.access(&cs).timers.service == IDLE_PRIO {
+            return Err(TimerError::NoService);
+        }
+        cs.deactivate();
+
+        TIMER_COMMANDS.push_timeout(task, (self.id, command), timeout).map_err(|_| TimerError::QueueFull)?;
+
+        let cs = CritSect::activate();
+        if let Some(id) = KERNEL.access(&cs).wake_timer_service() {
+            SysCalls::notify_task(id, cs);
+        }
+
+        Ok(())
+    }
+
+    /// Like command, but without a SysCall: woken is set if the Timer service Task has to preempt the running one, see yield_from_isr
+    #[cfg(feature = "timer_service")]
+    pub fn command_from_isr(&self, command: TimerCommand, woken: &mut bool) -> Result<(), TimerError> {
+        let cs = CritSect::activate();
+        if KERNEL.access(&cs).timers.service == IDLE_PRIO {
+            return Err(TimerError::NoService);
+        }
+        cs.deactivate();
+
+        TIMER_COMMANDS.push_from_isr((self.id, command), woken).map_err(|_| TimerError::QueueFull)?;
+
+        let cs = CritSect::activate();
+        let k = KERNEL.access(&cs);
+        if let Some(id) = k.wake_timer_service() {
+            k.tasks.idle(id);
+            *woken |= k.preempts(id);
+        }
+
+        Ok(())
+    }
+
+    pub const fn set_mode(&mut self, mode: TimerMode) {
+        // Set mode and reloads timer countdown
+        self.mode = mode;
         self.cntdwn = self.period.ticks();
 
         #[cfg(feature = "timers_bursts")]
@@ -599,6 +1475,9 @@ impl Timer {
         self.cntdwn -= 1;
         
         if self.cntdwn == 0 {
+            #[cfg(feature = "trace")]
+            trace::record(TraceEvent::TimerFire, IDLE_PRIO, self.id as usize, self.callback.addr());
+
             self.fire();
         }
 
@@ -609,18 +1488,18 @@ impl Timer {
     fn fire(&mut self) {
         match self.mode {
             TimerMode::OneShot => {
-                (self.callback)();
+                self.fired = self.fired.saturating_add(1);
                 self.mode = TimerMode::Expired;
             }
 
             TimerMode::Looping => {
-                (self.callback)();
+                self.fired = self.fired.saturating_add(1);
                 self.cntdwn = self.period.ticks();
             }
 
             #[cfg(feature = "timers_bursts")]
             TimerMode::LoopingBurst(ticks, bursts) => {
-                (self.callback)();
+                self.fired = self.fired.saturating_add(1);
                 self.bursts_cnt -= 1;
 
                 if self.bursts_cnt == 0 {
@@ -634,14 +1513,14 @@ impl Timer {
             }
 
             TimerMode::Counted(cnt) if cnt > 0 => {
-                (self.callback)();
+                self.fired = self.fired.saturating_add(1);
                 self.mode = TimerMode::Counted(cnt - 1);
                 self.cntdwn = self.period.ticks();
             }
 
             #[cfg(feature = "timers_bursts")]
             TimerMode::CountedBurst(cnt, ticks, bursts) if cnt > 0 => {
-                (self.callback)();
+                self.fired = self.fired.saturating_add(1);
                 self.bursts_cnt -= 1;
 
                 if self.bursts_cnt == 0 {
@@ -658,6 +1537,71 @@ impl Timer {
             _ => self.mode = TimerMode::Expired,
         }
     }
+
+    /// Calls the callback once for each firing since last call
+    fn run_fired(&mut self) {
+        for _ in 0..core::mem::take(&mut self.fired) {
+            self.callback.call();
+        }
+    }
+}
+
+/// Errors returned by Timer service APIs
+#[cfg(feature = "timer_service")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimerError {
+    /// No Timer service Task has been added
+    NoService,
+    /// A Timer service Task has already been added
+    ServiceAdded,
+    /// Kernel has no free slot for the Timer service Task
+    KernelFull,
+    /// Command queue has stayed full
+    QueueFull,
+}
+
+/// Commands sent to the Timer service Task, see Timer::command
+#[cfg(feature = "timer_service")]
+#[derive(Clone, Copy)]
+pub enum TimerCommand {
+    Start,
+    Stop,
+    /// Starts the Timer, with its countdown reloaded
+    Reset,
+    /// Starts the Timer with a new period
+    ChangePeriod(Duration),
+}
+
+/// Commands waiting for the Timer service Task
+#[cfg(feature = "timer_service")]
+pub const TIMER_COMMANDS_SIZE: usize = 8;
+
+#[cfg(feature = "timer_service")]
+static TIMER_COMMANDS: Queue<(u8, TimerCommand), TIMER_COMMANDS_SIZE> = Queue::new();
+
+/// Entry point of the Timer service Task, see Kernel::add_timer_service.
+/// Timer callbacks are called here, at the priority of the Task, so that they can block
+/// and do not lengthen SysTick. Commands are served before callbacks.
+#[cfg(feature = "timer_service")]
+pub fn timer_service(task: &mut Task) -> ! {
+    loop {
+        let _ = task.wait_notification(usize::MAX, ms::new(0));
+
+        while let Some((slot, command)) = TIMER_COMMANDS.pop_available() {
+            let cs = CritSect::activate();
+            KERNEL.access(&cs).timers.command(slot as usize, command);
+        }
+
+        loop {
+            let cs = CritSect::activate();
+            let Some(mut tim) = KERNEL.access(&cs).timers.take_fired() else {
+                break;
+            };
+            cs.deactivate();
+
+            tim.run_fired();
+        }
+    }
 }
 
 #[cfg(feature = "timers")]
@@ -665,6 +1609,12 @@ struct TimerList {
     list: [MaybeUninit<Timer>; BitVec::BITS],
     used: BitVec,
     active: BitVec,
+    /// Timers with callbacks waiting for the Timer service Task
+    #[cfg(feature = "timer_service")]
+    fired: BitVec,
+    /// Id of the Timer service Task, IDLE_PRIO if callbacks are called by SysTick
+    #[cfg(feature = "timer_service")]
+    service: usize,
 }
 
 #[cfg(feature = "timers")]
@@ -674,6 +1624,10 @@ impl TimerList {
             list: [const { MaybeUninit::zeroed() }; BitVec::BITS],
             used: BitVec::new(),
             active: BitVec::new(),
+            #[cfg(feature = "timer_service")]
+            fired: BitVec::new(),
+            #[cfg(feature = "timer_service")]
+            service: IDLE_PRIO,
         }
     }
 
@@ -682,7 +1636,7 @@ impl TimerList {
         unsafe { &mut *self.list[slot].as_mut_ptr() }
     }
 
-    fn add_timer(&mut self, period: Duration, callback: fn(), mode: TimerMode) -> Result<&Timer, ()> {
+    fn add_timer(&mut self, period: Duration, callback: TimerCallback, mode: TimerMode) -> Result<&Timer, ()> {
         let id = self.used.find_first_zero()?;
         let mut tim = Timer::new(id as u8, period, callback);
         tim.set_mode(mode);
@@ -696,21 +1650,103 @@ impl TimerList {
             return Err(());
         }
 
+        self.free(slot);
+        Ok(())
+    }
+
+    fn free(&mut self, slot: usize) {
         self.list[slot] = MaybeUninit::zeroed();
         self.used.clear(slot);
-        Ok(())
+        self.active.clear(slot);
+        #[cfg(feature = "timer_service")]
+        self.fired.clear(slot);
     }
 
-    fn tick_timers(&mut self) {
+    /// Ticks till the first active Timer fires
+    #[cfg(feature = "tickless")]
+    fn next_expiry(&mut self) -> Ticks {
+        let mut next = Ticks::MAX;
         for slot in self.active.into_iter() {
             let tim = self.get_timer(slot);
-            if TimerMode::Expired == tim.tick_and_fire() {
-                let id = tim.id as usize;
-                self.active.clear(id);
-                self.used.clear(id);
-                self.list[id] = MaybeUninit::zeroed();
+            if tim.cntdwn < next {
+                next = tim.cntdwn;
+            }
+        }
+
+        next
+    }
+
+    /// Countdowns are not expected to expire here: ticks are less than next_expiry()
+    #[cfg(feature = "tickless")]
+    fn skip_timers(&mut self, ticks: Ticks) {
+        for slot in self.active.into_iter() {
+            self.get_timer(slot).cntdwn -= ticks;
+        }
+    }
+
+    /// Callbacks are called here, unless there is a Timer service Task: then an expired Timer
+    /// keeps its slot till its last callbacks have been called.
+    fn tick_timers(&mut self) {
+        for slot in self.active.into_iter() {
+            let mode = self.get_timer(slot).tick_and_fire();
+
+            #[cfg(feature = "timer_service")]
+            if self.service != IDLE_PRIO {
+                if self.get_timer(slot).fired > 0 {
+                    self.fired.set(slot);
+                    if TimerMode::Expired == mode {
+                        self.active.clear(slot);
+                    }
+                } else if TimerMode::Expired == mode {
+                    self.free(slot);
+                }
+                continue;
+            }
+
+            self.get_timer(slot).run_fired();
+            if TimerMode::Expired == mode {
+                self.free(slot);
+            }
+        }
+    }
+
+    /// Takes the first Timer with callbacks to be called, leaving its firings in the returned copy
+    #[cfg(feature = "timer_service")]
+    fn take_fired(&mut self) -> Option<Timer> {
+        let slot = self.fired.find_first_set().ok()?;
+        self.fired.clear(slot);
+
+        let tim = self.get_timer(slot);
+        let fired = Timer { fired: core::mem::take(&mut tim.fired), ..*tim };
+        if TimerMode::Expired == tim.mode && !self.active.check(slot) {
+            self.free(slot);
+        }
+
+        Some(fired)
+    }
+
+    /// Commands to removed Timers are discarded
+    #[cfg(feature = "timer_service")]
+    fn command(&mut self, slot: usize, command: TimerCommand) {
+        if !self.used.check(slot) {
+            return;
+        }
+
+        let tim = self.get_timer(slot);
+        match command {
+            TimerCommand::Start => (),
+            TimerCommand::Stop => {
+                self.active.clear(slot);
+                return;
+            }
+            TimerCommand::Reset => tim.set_mode(tim.mode),
+            TimerCommand::ChangePeriod(period) => {
+                tim.period = period;
+                tim.set_mode(tim.mode);
             }
         }
+
+        self.active.set(slot);
     }
 }
 
@@ -722,11 +1758,20 @@ impl TimerList {
 trait SysCallFns: SysCallArgs {
     fn start_scheduler(task: &Task) -> !;
     fn set_task_idle(id: usize);
-    fn set_task_sleep(id: usize, ticks: Ticks);
+    fn set_task_sleep(id: usize, ticks: Ticks, cs: CritSect);
     fn set_task_stop(id: usize);
+    fn wait_notification(id: usize, ticks: Ticks, cs: CritSect);
+    fn notify_task(id: usize, cs: CritSect);
+    fn delete_task(task: &Task, slots: &AtomicBitVec, slot: usize, cs: CritSect);
+    fn suspend_task(id: usize, cs: CritSect);
+    fn resume_task(id: usize, cs: CritSect);
     fn meet_at_rendezvous(rndv: &Rendezvous, id: usize);
-    fn wait_semaphore(smph: &Semaphore, id: usize, ticks: Ticks);
+    fn wait_semaphore(smph: &Semaphore, id: usize, ticks: Ticks, cs: CritSect);
     fn release_semaphore(smph: &Semaphore, cs: CritSect);
+    fn wait_events(events: &EventGroup, id: usize, ticks: Ticks, cs: CritSect);
+    fn set_events(events: &EventGroup, cs: CritSect);
+    fn lock_mutex(mutex: &MutexLock, id: usize, ticks: Ticks, cs: CritSect);
+    fn unlock_mutex(mutex: &MutexLock, id: usize, cs: CritSect);
 }
 
 trait SysCallArgs {
@@ -759,6 +1804,15 @@ pub(crate) enum SysCalls {
     MeetAtRendezvous = 5,
     WaitSemaphore = 6,
     ReleaseSemaphore = 7,
+    LockMutex = 8,
+    UnlockMutex = 9,
+    WaitEvents = 10,
+    SetEvents = 11,
+    WaitNotification = 12,
+    NotifyTask = 13,
+    DeleteTask = 14,
+    SuspendTask = 15,
+    ResumeTask = 16,
 }
 
 impl Into<SysCalls> for u32 {
@@ -771,6 +1825,15 @@ impl Into<SysCalls> for u32 {
             5 => SysCalls::MeetAtRendezvous,
             6 => SysCalls::WaitSemaphore,
             7 => SysCalls::ReleaseSemaphore,
+            8 => SysCalls::LockMutex,
+            9 => SysCalls::UnlockMutex,
+            10 => SysCalls::WaitEvents,
+            11 => SysCalls::SetEvents,
+            12 => SysCalls::WaitNotification,
+            13 => SysCalls::NotifyTask,
+            14 => SysCalls::DeleteTask,
+            15 => SysCalls::SuspendTask,
+            16 => SysCalls::ResumeTask,
             _ => SysCalls::Nop,
         }
     }
@@ -786,6 +1849,15 @@ impl Into<SysCalls> for usize {
             5 => SysCalls::MeetAtRendezvous,
             6 => SysCalls::WaitSemaphore,
             7 => SysCalls::ReleaseSemaphore,
+            8 => SysCalls::LockMutex,
+            9 => SysCalls::UnlockMutex,
+            10 => SysCalls::WaitEvents,
+            11 => SysCalls::SetEvents,
+            12 => SysCalls::WaitNotification,
+            13 => SysCalls::NotifyTask,
+            14 => SysCalls::DeleteTask,
+            15 => SysCalls::SuspendTask,
+            16 => SysCalls::ResumeTask,
             _ => SysCalls::Nop,
         }
     }
@@ -805,7 +1877,6 @@ impl SysCallFns for SysCalls {
     fn set_task_idle(id: usize) {
         unsafe {
             let cs = CritSect::activate(); 
-            KERNEL.access(&cs).running().context.save();
             SysCalls::set0(id);
             cs.deactivate();
             SysCalls::SetTaskIdle.call();  
@@ -813,10 +1884,8 @@ impl SysCallFns for SysCalls {
     }
 
     #[inline(always)]
-    fn set_task_sleep(id: usize, ticks: Ticks) {
+    fn set_task_sleep(id: usize, ticks: Ticks, cs: CritSect) {
         unsafe { 
-            let cs = CritSect::activate(); 
-            KERNEL.access(&cs).running().context.save();
             SysCalls::set0(id);
             SysCalls::set1(ticks as usize);
             cs.deactivate();
@@ -828,7 +1897,6 @@ impl SysCallFns for SysCalls {
     fn set_task_stop(id: usize) {
         unsafe { 
             let cs = CritSect::activate(); 
-            KERNEL.access(&cs).running().context.save();
             SysCalls::set0(id);
             cs.deactivate();
             SysCalls::SetTaskStop.call();
@@ -836,44 +1904,129 @@ impl SysCallFns for SysCalls {
     }
 
     #[inline(always)]
-    fn meet_at_rendezvous(rndv: &Rendezvous, id: usize) {
-        unsafe {
-            let cs = CritSect::activate(); 
-            KERNEL.access(&cs).running().context.save();
-            SysCalls::set0(rndv as *const Rendezvous as usize);
-            SysCalls::set1(id);
+    fn wait_notification(id: usize, ticks: Ticks, cs: CritSect) {
+        unsafe { 
+            SysCalls::set0(id);
+            SysCalls::set1(ticks as usize);
             cs.deactivate();
-            SysCalls::MeetAtRendezvous.call();  
-        } 
+            SysCalls::WaitNotification.call();
+        }  
     }
 
     #[inline(always)]
-    fn wait_semaphore(smph: &Semaphore, id: usize, ticks: Ticks) {
+    fn notify_task(id: usize, cs: CritSect) {
         unsafe { 
-            let cs = CritSect::activate(); 
-            KERNEL.access(&cs).running().context.save();
-            SysCalls::set0(smph as *const Semaphore as usize);
-            SysCalls::set1(id);
-            SysCalls::set2(ticks as usize);
+            SysCalls::set0(id);
             cs.deactivate();
-            SysCalls::WaitSemaphore.call();
-        }
+            SysCalls::NotifyTask.call();
+        }  
     }
 
     #[inline(always)]
-    fn release_semaphore(smph: &Semaphore, cs: CritSect) {
+    fn delete_task(task: &Task, slots: &AtomicBitVec, slot: usize, cs: CritSect) {
         unsafe { 
-            KERNEL.access(&cs).running().context.save();
-            SysCalls::set0(smph as *const Semaphore as usize);
+            SysCalls::set0(task as *const Task as usize);
+            SysCalls::set1(slots as *const AtomicBitVec as usize);
+            SysCalls::set2(slot);
             cs.deactivate();
-            SysCalls::ReleaseSemaphore.call();
+            SysCalls::DeleteTask.call();
         }  
     }
-}
-
-//*********************************************************************************************************************
-// KERNEL
-//*********************************************************************************************************************
+
+    #[inline(always)]
+    fn suspend_task(id: usize, cs: CritSect) {
+        unsafe { 
+            SysCalls::set0(id);
+            cs.deactivate();
+            SysCalls::SuspendTask.call();
+        }  
+    }
+
+    #[inline(always)]
+    fn resume_task(id: usize, cs: CritSect) {
+        unsafe { 
+            SysCalls::set0(id);
+            cs.deactivate();
+            SysCalls::ResumeTask.call();
+        }  
+    }
+
+    #[inline(always)]
+    fn meet_at_rendezvous(rndv: &Rendezvous, id: usize) {
+        unsafe {
+            let cs = CritSect::activate(); 
+            SysCalls::set0(rndv as *const Rendezvous as usize);
+            SysCalls::set1(id);
+            cs.deactivate();
+            SysCalls::MeetAtRendezvous.call();  
+        } 
+    }
+
+    #[inline(always)]
+    fn wait_semaphore(smph: &Semaphore, id: usize, ticks: Ticks, cs: CritSect) {
+        unsafe { 
+            SysCalls::set0(smph as *const Semaphore as usize);
+            SysCalls::set1(id);
+            SysCalls::set2(ticks as usize);
+            cs.deactivate();
+            SysCalls::WaitSemaphore.call();
+        }
+    }
+
+    #[inline(always)]
+    fn release_semaphore(smph: &Semaphore, cs: CritSect) {
+        unsafe { 
+            SysCalls::set0(smph as *const Semaphore as usize);
+            cs.deactivate();
+            SysCalls::ReleaseSemaphore.call();
+        }  
+    }
+
+    #[inline(always)]
+    fn wait_events(events: &EventGroup, id: usize, ticks: Ticks, cs: CritSect) {
+        unsafe { 
+            SysCalls::set0(events as *const EventGroup as usize);
+            SysCalls::set1(id);
+            SysCalls::set2(ticks as usize);
+            cs.deactivate();
+            SysCalls::WaitEvents.call();
+        }
+    }
+
+    #[inline(always)]
+    fn set_events(events: &EventGroup, cs: CritSect) {
+        unsafe { 
+            SysCalls::set0(events as *const EventGroup as usize);
+            cs.deactivate();
+            SysCalls::SetEvents.call();
+        }  
+    }
+
+    #[inline(always)]
+    fn lock_mutex(mutex: &MutexLock, id: usize, ticks: Ticks, cs: CritSect) {
+        unsafe { 
+            SysCalls::set0(mutex as *const MutexLock as usize);
+            SysCalls::set1(id);
+            SysCalls::set2(ticks as usize);
+            cs.deactivate();
+            SysCalls::LockMutex.call();
+        }  
+    }
+
+    #[inline(always)]
+    fn unlock_mutex(mutex: &MutexLock, id: usize, cs: CritSect) {
+        unsafe { 
+            SysCalls::set0(mutex as *const MutexLock as usize);
+            SysCalls::set1(id);
+            cs.deactivate();
+            SysCalls::UnlockMutex.call();
+        }  
+    }
+}
+
+//*********************************************************************************************************************
+// KERNEL
+//*********************************************************************************************************************
 
 /// Scheduler keeps in memory variables used to complete context switching.
 /// This way we avoid using a series of 'unsafe' to modify static global data.
@@ -884,6 +2037,23 @@ pub struct Kernel {
     /// Total system ticks till system started
     ticks: SystemTicks,
 
+    /// Round-robin quantum among Tasks of the same priority, 0 disables time slicing
+    time_slice: Ticks,
+    /// Ticks the running Task has been executed for since it got the CPU
+    slice_ticks: Ticks,
+
+    /// Decides what to do with a Task that has overflowed its stack, Tasks are stopped if None
+    overflow_hook: Option<StackOverflowHook>,
+
+    /// Decides what to do with a Task that has faulted, Tasks are stopped if None
+    fault_hook: Option<FaultHook>,
+
+    /// Where fault reports are printed
+    console: Option<ConsoleWrite>,
+
+    /// Running Task must be restarted when it is switched out
+    restart: bool,
+
     /// Core peripherals - depends on CPU HW
     core: CorePeripherals,
 
@@ -893,6 +2063,10 @@ pub struct Kernel {
     /// Timers list
     #[cfg(feature = "timers")]
     timers: TimerList,
+
+    /// Run time of Tasks and context switches
+    #[cfg(feature = "cpu_stats")]
+    stats: Stats,
 }
 
 impl Kernel {
@@ -901,16 +2075,25 @@ impl Kernel {
             running: MaybeUninit::zeroed(),
             next: MaybeUninit::zeroed(),
             ticks: 0,
+            time_slice: 0,
+            slice_ticks: 0,
+            overflow_hook: None,
+            fault_hook: None,
+            console: None,
+            restart: false,
             core: CorePeripherals::new(),
             tasks: TaskList::new(),
             #[cfg(feature = "timers")]
             timers: TimerList::new(),
+            #[cfg(feature = "cpu_stats")]
+            stats: Stats::new(),
         }
     }
 
     #[inline(always)]
     pub fn init(&mut self, cs: CritSect) -> ! {
-        // Setup of CPU core peripherals
+        // Setup of CPU core peripherals. Host simulation has no clock tree to configure.
+        #[cfg(not(native))]
         self.setup_clock();
 
         self.core.setup();
@@ -921,7 +2104,7 @@ impl Kernel {
             // Sequence to avoid compiler warnings
             let idle = &raw mut IDLE_TASK;
             self.running.write(idle);
-            (&mut *idle).setup();
+            (&mut *idle).init_stack();
             
             cs.deactivate();
             SysCalls::start_scheduler(self.running());
@@ -929,19 +2112,118 @@ impl Kernel {
         // We should never arrive here, as CPU is under Scheluder control
     }
 
+    /// Tasks are given one of BitVec::BITS slots whatever their priority: Err when all slots are taken
     #[inline]
     pub const fn add_task(&mut self, task: &'static Task) -> Result<(), ()> {
-        self.tasks.add_task(task)
+        let res = self.tasks.add_task(task);
+        #[cfg(feature = "cpu_stats")]
+        if res.is_ok() {
+            self.stats.clear(task.id);
+        }
+
+        res
     }
 
     #[inline]
     pub const fn remove_task(&mut self, task: &'static Task) -> Result<(), ()> {
+        #[cfg(feature = "timer_service")]
+        if task.id == self.timers.service {
+            self.timers.service = IDLE_PRIO;
+        }
+
         self.tasks.remove_task(task)
     }
 
+    /// Sets the round-robin quantum among Tasks of the same priority: 0 disables time slicing,
+    /// so that a Task keeps the CPU till it blocks or an higher priority one is ready.
+    #[inline]
+    pub const fn set_time_slice(&mut self, ticks: Ticks) {
+        self.time_slice = ticks;
+    }
+
+    /// Sets the hook called when a Task overflows its stack, checked at every context switch
+    #[inline]
+    pub const fn set_overflow_hook(&mut self, hook: StackOverflowHook) {
+        self.overflow_hook = Some(hook);
+    }
+
+    /// Sets the hook called when a Task faults, to stop it, restart it or reset the system
+    #[inline]
+    pub const fn set_fault_hook(&mut self, hook: FaultHook) {
+        self.fault_hook = Some(hook);
+    }
+
+    /// Sets the console where the Kernel prints its reports
+    #[inline]
+    pub const fn set_console(&mut self, console: ConsoleWrite) {
+        self.console = Some(console);
+    }
+
+    /// Prints on the console, if any
+    pub(crate) fn print(&self, args: fmt::Arguments) {
+        if let Some(console) = self.console {
+            let _ = Console(console).write_fmt(args);
+        }
+    }
+
+    /// Declares an MPU region of a Task, i.e. a shared object it can only read or must not access.
+    /// Size must be a power of two of at least 32 bytes and base must be aligned to it.
+    /// Peripheral registers must be declared as Device or StronglyOrdered memory, RAM and flash as Normal.
+    /// Regions are programmed at every context switch, together with the stack guard of the Task.
+    #[cfg(feature = "mpu_enabled")]
+    pub fn set_task_region(&mut self, task: &Task, region: usize, base: usize, size: usize, access: MpuAccess, memory: MpuMemory) -> Result<(), MpuError> {
+        if task.id == IDLE_PRIO {
+            return Err(MpuError::NotAdded);
+        }
+
+        self.tasks.get_ref_mut(task.id).mpu.set_region(region, base, size, access, memory)?;
+        if self.started() && self.running().id == task.id {
+            self.running().mpu.load();
+        }
+        Ok(())
+    }
+
+    /// Removes an MPU region declared with set_task_region
+    #[cfg(feature = "mpu_enabled")]
+    pub fn clear_task_region(&mut self, task: &Task, region: usize) -> Result<(), MpuError> {
+        if task.id == IDLE_PRIO {
+            return Err(MpuError::NotAdded);
+        }
+
+        self.tasks.get_ref_mut(task.id).mpu.clear_region(region)?;
+        if self.started() && self.running().id == task.id {
+            self.running().mpu.load();
+        }
+        Ok(())
+    }
+
+    /// CPU load of Tasks over the last window, context switches and preemptions
+    #[cfg(feature = "cpu_stats")]
+    pub fn stats(&self) -> CpuStats {
+        self.stats.snapshot()
+    }
+
+    /// Time the Task has been running since it has been added, in counts of the statistics time base.
+    /// None if the Task has not been added.
+    #[cfg(feature = "cpu_stats")]
+    pub fn run_time(&self, task: &Task) -> Option<u64> {
+        if task.id == IDLE_PRIO {
+            return None;
+        }
+
+        Some(self.stats.run_time(task.id))
+    }
+
     #[cfg(feature = "timers")]
     #[inline]
     pub fn new_timer(&mut self, period: Duration, callback: fn(), active: bool, mode: TimerMode) -> Result<&Timer, ()> {
+        self.new_timer_with(period, TimerCallback::Fn(callback), active, mode)
+    }
+
+    /// Like new_timer, with a callback carrying its context or a TimerHandler
+    #[cfg(feature = "timers")]
+    #[allow(clippy::result_unit_err)] // Same error as new_timer: no free Timer slot
+    pub fn new_timer_with(&mut self, period: Duration, callback: TimerCallback, active: bool, mode: TimerMode) -> Result<&Timer, ()> {
         let res = self.timers.add_timer(period, callback, mode);
         if let Ok(tim) = res {
             if active == true {
@@ -958,6 +2240,67 @@ impl Kernel {
         self.timers.remove_timer(slot)
     }
 
+    /// Adds the Timer service Task, created with timer_service as entry point: from now on
+    /// Timer callbacks are called by it instead of SysTick, and Timers accept commands.
+    #[cfg(feature = "timer_service")]
+    pub fn add_timer_service(&mut self, task: &'static Task) -> Result<(), TimerError> {
+        if self.timers.service != IDLE_PRIO {
+            return Err(TimerError::ServiceAdded);
+        }
+
+        self.add_task(task).map_err(|_| TimerError::KernelFull)?;
+        self.timers.service = task.id;
+        Ok(())
+    }
+
+    /// Notifies the Timer service Task, returns its id if it was waiting for the notification
+    #[cfg(feature = "timer_service")]
+    fn wake_timer_service(&mut self) -> Option<usize> {
+        match self.tasks.notify(self.timers.service, 1, NotifyAction::SetBits) {
+            Ok(true) => Some(self.timers.service),
+            _ => None,
+        }
+    }
+
+    /// Scheduler has been started by init
+    #[inline]
+    fn started(&self) -> bool {
+        !unsafe { self.running.assume_init_read() }.is_null()
+    }
+
+    /// Priority of the running Task, IDLE_PRIO before the scheduler is started.
+    /// Kernel is accessed without critical section: interrupts must be disabled already.
+    pub(crate) fn running_prio() -> usize {
+        let k = unsafe { KERNEL.access_unsafe() };
+        if k.started() {
+            k.running().prio
+        } else {
+            IDLE_PRIO
+        }
+    }
+
+    /// Task woken up by an ISR must preempt the running one: it can run and it has an higher priority
+    fn preempts(&self, id: usize) -> bool {
+        if !self.started() || !self.tasks.runnable().check(id) {
+            return false;
+        }
+
+        let running = self.running().id;
+        running == IDLE_PRIO || self.tasks.effective[id] > self.tasks.effective[running]
+    }
+
+    /// Id of the running Task, IDLE_PRIO for the idle Task or before the scheduler is started.
+    /// Kernel is read without critical section, like ISRs do: the running Task can't change meanwhile.
+    #[cfg(feature = "trace")]
+    pub(crate) fn running_id() -> usize {
+        let k = unsafe { KERNEL.access_unsafe() };
+        if k.started() {
+            k.running().id
+        } else {
+            IDLE_PRIO
+        }
+    }
+
     #[inline]
     const fn running(&self) -> &Task {
         unsafe { &*self.running.as_ptr().read() }
@@ -976,16 +2319,77 @@ impl Kernel {
     #[inline]
     pub(crate) fn inc_system_ticks(&mut self) {
         self.ticks += 1;
+        self.slice_ticks = self.slice_ticks.saturating_add(1);
         self.tasks.tick_sleeping();
 
         #[cfg(feature = "timers")]
         self.timers.tick_timers();
+
+        #[cfg(feature = "timer_service")]
+        if self.timers.fired.is_populated() {
+            if let Some(id) = self.wake_timer_service() {
+                self.tasks.idle(id);
+            }
+        }
+
+        #[cfg(feature = "cpu_stats")]
+        self.stats.tick(self.running().id, 1, CorePeripherals::cycles());
+    }
+
+    /// Ticks till the first Task or Timer needs the kernel
+    #[cfg(feature = "tickless")]
+    fn next_wakeup(&mut self) -> Ticks {
+        let next = self.tasks.next_wakeup();
+
+        #[cfg(feature = "timers")]
+        let next = next.min(self.timers.next_expiry());
+
+        next
+    }
+
+    /// Accounts for ticks elapsed while SysTick was suppressed
+    #[cfg(feature = "tickless")]
+    fn skip_ticks(&mut self, ticks: Ticks) {
+        self.ticks += ticks as SystemTicks;
+        self.tasks.skip_sleeping(ticks);
+
+        #[cfg(feature = "timers")]
+        self.timers.skip_timers(ticks);
+
+        #[cfg(feature = "cpu_stats")]
+        self.stats.tick(self.running().id, ticks, CorePeripherals::cycles());
+    }
+
+    /// Idle sleep with SysTick suppressed till next wake-up. The last tick is always counted
+    /// by SysTick, so that expired countdowns are handled as usual.
+    #[cfg(feature = "tickless")]
+    fn tickless_sleep() {
+        let cs = CritSect::activate();
+        let k = KERNEL.access(&cs);
+        let next = k.next_wakeup();
+
+        if k.tasks.runnable().is_populated() {
+            // A Task has been woken up by an interrupt: scheduler will run at critical section exit
+        } else if next > 1 {
+            let skipped = k.core.suppress_ticks(next);
+            k.skip_ticks(skipped);
+        } else {
+            cs.deactivate();
+            Kernel::core_sleep();
+        }
     }
 
     pub(crate) fn schedule_next(&mut self) {
-        match (self.running().prio, self.tasks.next_waiting()) {
+        let running = self.running().id;
+        let rotate = self.time_slice != 0 && self.slice_ticks >= self.time_slice;
+        if rotate {
+            self.slice_ticks = 0;
+        }
+
+        match (running, self.tasks.next_waiting(running, rotate)) {
             // New task to be scheduled
             (run, Ok(next)) if next != run => {
+                self.slice_ticks = 0;
                 self.next = MaybeUninit::new(self.tasks.get_ref(next));
                 //self.core.sleep_on_exit(false);
                 self.request_context_switch();
@@ -1002,10 +2406,10 @@ impl Kernel {
                 self.request_context_switch();
             }
 
-            // Same task to execute
-            _ => { 
-                Kernel::start_task(self.running());
-            }
+            // Same task to execute: simply return from exception. Its context is left untouched by the
+            // SysCall, as it is saved and loaded by PendSV only when the Task changes, so there is
+            // no need to start it again as it was done when SysCalls switched context by themselves.
+            _ => {}
         }
     }
 
@@ -1016,6 +2420,10 @@ impl Kernel {
     /// It is executed in privileged mode at maximum priority level, so no other
     /// interrupt can preempt it.
     pub(crate) fn handle_syscall(&mut self, call: SysCalls) {
+        // SysCall arguments are read from registers by each SysCall, so only its number is recorded
+        #[cfg(feature = "trace")]
+        trace::record(TraceEvent::SysCall, self.running().id, call as usize, 0);
+
         match call {
             SysCalls::Nop => {},
 
@@ -1043,6 +2451,28 @@ impl Kernel {
                 self.schedule_next();
             },
 
+            SysCalls::WaitNotification => {
+                let id = SysCalls::arg0();
+                let ticks: Ticks = SysCalls::arg1() as u32;
+
+                // Notification could have been sent before this SysCall
+                if !self.tasks.get_ref(id).notified {
+                    self.tasks.notifying.set(id);
+                    if ticks == 0 {
+                        self.tasks.stop(id);
+                    } else {
+                        self.tasks.sleep(id, ticks);
+                    }
+                }
+                self.schedule_next();
+            },
+
+            SysCalls::NotifyTask => {
+                let id = SysCalls::arg0();
+                self.tasks.idle(id);
+                self.schedule_next();
+            },
+
             SysCalls::MeetAtRendezvous => {
                 // Safety: we trust that the pointer passed is valid due to Rust's ownership rules
                 // SysCall::MeetAtRendezvous is called with a &Rendezvous reference
@@ -1050,13 +2480,16 @@ impl Kernel {
                 let id = SysCalls::arg1();
                 
                 rndv.arrived.set(id);
-                if rndv.arrived.superset_of(&rndv.mask) {
-                    let arrived = rndv.arrived.raw() as u32;
+                let arrived = BitVec::init(rndv.arrived.raw());
+                if arrived.superset_of(&rndv.mask()) {
                     rndv.arrived.reset();
                     
-                    self.tasks.ready |= arrived.into();
-                    self.tasks.sleeping &= (!arrived).into();
+                    for id in &arrived {
+                        self.tasks.get_ref_mut(id).rendezvous.set(None);
+                        self.tasks.idle(id);
+                    }
                 } else {
+                    self.tasks.get_ref_mut(id).rendezvous.set(Some(rndv));
                     self.tasks.stop(id);
                 }
 
@@ -1070,71 +2503,270 @@ impl Kernel {
                 let id = SysCalls::arg1();
                 let ticks: Ticks = SysCalls::arg2() as u32;
 
-                smph.locked.set(id);
-                self.tasks.sleep(id, ticks);
-                self.tasks.get_ref_mut(id).semaphore.set(Some(smph));
+                // Semaphore could have been released before this SysCall
+                if smph.locked.check(id) {
+                    #[cfg(feature = "trace")]
+                    trace::record(TraceEvent::Block, id, 0, smph as *const Semaphore as usize);
+
+                    if ticks == 0 {
+                        self.tasks.stop(id);
+                    } else {
+                        self.tasks.sleep(id, ticks);
+                    }
+                    self.tasks.get_ref_mut(id).semaphore.set(Some(smph));
+                }
                 self.schedule_next();
             },
 
             SysCalls::ReleaseSemaphore => {
                 let smph = unsafe { &*(SysCalls::arg0() as *const Semaphore) };
-                if let Ok(id) = smph.locked.find_highest_set() {
-                    smph.locked.clear(id);
-                    self.tasks.idle(id);
+                smph.wake_waiter(&mut self.tasks);
+                self.schedule_next();
+            },
+
+            SysCalls::WaitEvents => {
+                // Safety: we trust that the pointer passed is valid due to Rust's ownership rules
+                // SysCall::WaitEvents is called with a &EventGroup reference
+                let events = unsafe { &*(SysCalls::arg0() as *const EventGroup) };
+                let id = SysCalls::arg1();
+                let ticks: Ticks = SysCalls::arg2() as u32;
+
+                self.tasks.get_ref_mut(id).events.set(Some(events));
+
+                // Flags could have been set before this SysCall
+                let flags = BitVec::init(events.flags.raw());
+                if let Some(cleared) = self.tasks.wake_on_events(id, flags) {
+                    events.flags.write_raw(cleared.difference(&flags).raw());
+                } else {
+                    events.waiting.set(id);
+                    if ticks == 0 {
+                        self.tasks.stop(id);
+                    } else {
+                        self.tasks.sleep(id, ticks);
+                    }
                 }
+
                 self.schedule_next();
-            }
-        }
-    }
+            },
 
-    #[no_mangle]
-    pub(crate) fn switch_to_next(&mut self) {
-        unsafe {
-            // self.running().context.save(); // should be already saved by SysCall handler
-            self.running_mut().update_watermark();
+            SysCalls::SetEvents => {
+                let events = unsafe { &*(SysCalls::arg0() as *const EventGroup) };
+                events.wake_waiters(&mut self.tasks);
+                self.schedule_next();
+            },
 
-            self.running = self.next;
-            self.next = MaybeUninit::new(&raw const IDLE_TASK);
-            self.running().context.load();
-        }
-    }
-}
+            SysCalls::LockMutex => {
+                // Safety: we trust that the pointer passed is valid due to Rust's ownership rules
+                // SysCall::LockMutex is called with a &MutexLock reference
+                let mutex = unsafe { &*(SysCalls::arg0() as *const MutexLock) };
+                let id = SysCalls::arg1();
+                let ticks: Ticks = SysCalls::arg2() as u32;
 
-//*********************************************************************************************************************
-// SEMAPHORES, RENDEZVOUS and MUTEXes
-//*********************************************************************************************************************
+                // Owner could have released the Mutex before this SysCall
+                if let Some(owner) = mutex.owner() {
+                    mutex.waiting.set(id);
+                    if ticks == 0 {
+                        self.tasks.stop(id);
+                    } else {
+                        self.tasks.sleep(id, ticks);
+                    }
+                    self.tasks.get_ref_mut(id).mutex.set(Some(mutex));
+                    self.tasks.boost(owner, self.tasks.effective[id]);
+                } else {
+                    self.tasks.take_mutex(mutex, id);
+                }
+                self.schedule_next();
+            },
 
-#[derive(Debug)]
-pub struct Semaphore {
-    locked: AtomicBitVec,
-}
+            SysCalls::UnlockMutex => {
+                let mutex = unsafe { &*(SysCalls::arg0() as *const MutexLock) };
+                let id = SysCalls::arg1();
 
-impl Default for Semaphore {
-    fn default() -> Self {
-        Self::new()
-    }
-}
+                self.tasks.release_mutex(mutex, id);
+                self.schedule_next();
+            },
 
-impl Semaphore {
-    pub const fn new() -> Self {
-        Self {
-            locked: AtomicBitVec::new(),
+            SysCalls::DeleteTask => {
+                // Safety: we trust that the pointers passed are valid due to Rust's ownership rules
+                // SysCall::DeleteTask is called with a &Task and the &AtomicBitVec of its pool slots
+                let task = unsafe { &*(SysCalls::arg0() as *const Task) };
+                let slots = unsafe { &*(SysCalls::arg1() as *const AtomicBitVec) };
+                let slot = SysCalls::arg2();
+                let id = task.id;
+
+                if id != IDLE_PRIO {
+                    self.tasks.unlink(id);
+                    let _ = self.tasks.remove_task(task);
+                }
+                // Memory is reclaimed here, as a Task deleting itself never comes back
+                slots.clear(slot);
+
+                if core::ptr::eq(self.running(), task) {
+                    self.leave_running();
+                } else {
+                    self.schedule_next();
+                }
+            },
+
+            SysCalls::SuspendTask => {
+                let id = SysCalls::arg0();
+                self.tasks.suspend(id);
+                self.schedule_next();
+            },
+
+            SysCalls::ResumeTask => {
+                let id = SysCalls::arg0();
+                let _ = self.tasks.resume(id);
+                self.schedule_next();
+            },
         }
     }
 
-    pub fn acquire(&self, task: &Task) {
-        // A Critical section is not required as Semaphore is implmented as an atomic bitvector
-        self.locked.set(task.prio);
-        SysCalls::set_task_stop(task.prio);
+    /// Running Task can't be kept running, i.e. it has been deleted: PendSV still saves its context
+    /// into the TCB, but it is never loaded again. Idle Task is the fallback
+    fn leave_running(&mut self) {
+        self.next = match self.tasks.next_waiting(IDLE_PRIO, false) {
+            Ok(next) => MaybeUninit::new(self.tasks.get_ref(next)),
+            Err(()) => MaybeUninit::new(&raw const IDLE_TASK),
+        };
+        self.request_context_switch();
     }
 
-    pub fn wait(&self, task: &mut Task, timeout: ms) -> Result<(), ()> {
-        SysCalls::wait_semaphore(self, task.prio, timeout.into());
-        
-        if KERNEL.read().tasks.sleep_time[task.prio] != 0 {
-            Ok(())
-        } else {
-            Err(())
+    /// Running Task has faulted: it is contained before it can corrupt the Kernel.
+    /// The fault is reported, then hitting the stack guard is a stack overflow, otherwise the fault hook decides.
+    /// The system is reset if the idle Task faults, as there would be nothing else to run,
+    /// or if the fault is raised in handler mode, as the Kernel or an ISR can't be contained.
+    fn fault(&mut self, kind: FaultKind, frame: &ExceptionFrame, handler: bool) {
+        let report = FaultReport::new(kind, frame, self.running(), handler);
+        self.print(format_args!("{}", report));
+        crash::save_fault(&report);
+
+        if handler {
+            crash::save_reset(ResetReason::Fault, report.prio);
+            Self::system_reset();
+        }
+
+        let id = report.task;
+
+        #[cfg(feature = "mpu_enabled")]
+        if id != IDLE_PRIO && kind == FaultKind::Memory && report.regs.stack_fault(&self.running().mpu) {
+            MpuContext::unload();
+            self.restart = self.stack_overflow() == OverflowAction::Restart;
+            self.leave_running();
+            return;
+        }
+
+        let action = match self.fault_hook {
+            Some(hook) => hook(&report),
+            None => FaultAction::Stop,
+        };
+
+        if id == IDLE_PRIO || action == FaultAction::Reset {
+            crash::save_reset(ResetReason::Fault, report.prio);
+            Self::system_reset();
+        }
+
+        self.tasks.unlink(id);
+        self.tasks.stop(id);
+        self.restart = action == FaultAction::Restart;
+        self.leave_running();
+    }
+
+    /// Running Task has overflowed its stack: it is stopped, the overflow hook tells if it must be restarted
+    fn stack_overflow(&mut self) -> OverflowAction {
+        let id = self.running().id;
+        let action = match self.overflow_hook {
+            Some(hook) => hook(self.running()),
+            None => OverflowAction::Stop,
+        };
+
+        self.tasks.unlink(id);
+        self.tasks.stop(id);
+        action
+    }
+
+    #[no_mangle]
+    pub(crate) fn switch_to_next(&mut self) {
+        // Running Task context has been saved by PendSV (or by the trap entry), before calling this
+        // Next Task regions are set first, so that a restarted Task stack can be painted again
+        #[cfg(feature = "mpu_enabled")]
+        self.next().mpu.load();
+
+        let id = self.running().id;
+        #[cfg(feature = "trace")]
+        trace::record(TraceEvent::TaskSwitch, self.next().id, 0, id);
+
+        #[cfg(feature = "cpu_stats")]
+        self.stats.switch(id, id != IDLE_PRIO && self.tasks.runnable().check(id), CorePeripherals::cycles());
+
+        if id != IDLE_PRIO && self.running().stack_overflowed() && self.stack_overflow() == OverflowAction::Restart {
+            self.restart = true;
+        }
+
+        // Running Task context has been saved already, so it can be overwritten by a fresh one
+        if self.restart {
+            self.restart = false;
+            self.running_mut().init_stack();
+            self.tasks.idle(id);
+        }
+
+        self.running = self.next;
+        self.next = MaybeUninit::new(&raw const IDLE_TASK);
+        // Cortex-M context is loaded by PendSV itself, after this returns
+        #[cfg(not(cortex_m))]
+        unsafe { self.running().context.load() };
+    }
+}
+
+//*********************************************************************************************************************
+// SEMAPHORES, RENDEZVOUS, EVENT GROUPS and MUTEXes
+//*********************************************************************************************************************
+
+#[derive(Debug)]
+pub struct Semaphore {
+    locked: AtomicBitVec,
+}
+
+impl Default for Semaphore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Semaphore {
+    pub const fn new() -> Self {
+        Self {
+            locked: AtomicBitVec::new(),
+        }
+    }
+
+    pub fn acquire(&self, task: &Task) {
+        let cs = CritSect::activate();
+        self.acquire_cs(task, cs);
+    }
+
+    /// Waits with no timeout: the check of the protected object and the waiting mark share the critical section,
+    /// then the SysCall stops the Task only if no release has come in between, i.e. from an ISR
+    fn acquire_cs(&self, task: &Task, cs: CritSect) {
+        let _ = self.wait_cs(task, 0, cs);
+    }
+
+    pub fn wait(&self, task: &mut Task, timeout: ms) -> Result<(), ()> {
+        let cs = CritSect::activate();
+        self.wait_cs(task, timeout.into(), cs)
+    }
+
+    /// Task is marked as waiting before leaving the critical section, so that a release coming
+    /// from an ISR before the SysCall is not lost. Ticks equal to 0 means no timeout.
+    fn wait_cs(&self, task: &Task, ticks: Ticks, cs: CritSect) -> Result<(), ()> {
+        self.locked.set(task.id);
+        SysCalls::wait_semaphore(self, task.id, ticks, cs);
+
+        // Release drops the Semaphore reference, timeout keeps it
+        let cs = CritSect::activate();
+        match KERNEL.access(&cs).tasks.get_ref_mut(task.id).semaphore.take() {
+            Some(_) => Err(()),
+            None => Ok(()),
         }
     }
 
@@ -1146,41 +2778,413 @@ impl Semaphore {
     fn release_cs(&self, cs: CritSect) {
         SysCalls::release_semaphore(self, cs);
     }
+
+    /// Like release, but without a SysCall: woken is set if the Task has to preempt the running one, see yield_from_isr
+    pub fn release_from_isr(&self, woken: &mut bool) {
+        let cs = CritSect::activate();
+        self.release_from_isr_cs(&cs, woken);
+    }
+
+    fn release_from_isr_cs(&self, cs: &CritSect, woken: &mut bool) {
+        let k = KERNEL.access(cs);
+        if let Some(id) = self.wake_waiter(&mut k.tasks) {
+            *woken |= k.preempts(id);
+        }
+    }
+
+    /// Wakes up the waiting Task the Semaphore is handed over to, if any
+    fn wake_waiter(&self, tasks: &mut TaskList) -> Option<usize> {
+        let id = tasks.next_waiter(self.locked.raw().into())?;
+        self.locked.clear(id);
+        tasks.get_ref_mut(id).semaphore.set(None);
+        tasks.idle(id);
+        #[cfg(feature = "trace")]
+        trace::record(TraceEvent::Unblock, id, 0, self as *const Semaphore as usize);
+        Some(id)
+    }
+}
+
+/// Errors returned by CountingSemaphore APIs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SemaphoreError {
+    /// No token is available
+    WouldBlock,
+    /// No token has been given before timeout expiration
+    Timeout,
+    /// Count is already at its maximum value
+    Full,
+}
+
+/// Semaphore that counts releases not yet taken, up to a maximum value.
+/// When a Task is waiting, a given token is handed over to it instead of being counted.
+pub struct CountingSemaphore {
+    smph: Semaphore,
+    count: Cell<usize>,
+    max: usize,
+}
+
+unsafe impl Sync for CountingSemaphore {}
+
+impl CountingSemaphore {
+    pub const fn new(initial: usize, max: usize) -> Self {
+        Self {
+            smph: Semaphore::new(),
+            count: Cell::new(if initial > max { max } else { initial }),
+            max,
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.count.get()
+    }
+
+    /// Takes a token only if available, without waiting
+    pub fn try_take(&self) -> Result<(), SemaphoreError> {
+        let _cs = CritSect::activate();
+        self.take_count()
+    }
+
+    /// Waits till a token is available
+    pub fn take(&self, task: &mut Task) {
+        let cs = CritSect::activate();
+        if self.take_count().is_err() {
+            // Without timeout the Task can only be woken up by a give
+            let _ = self.smph.wait_cs(task, 0, cs);
+        }
+    }
+
+    pub fn take_timeout(&self, task: &mut Task, timeout: ms) -> Result<(), SemaphoreError> {
+        let ticks: Ticks = timeout.into();
+        let cs = CritSect::activate();
+        if self.take_count().is_ok() {
+            return Ok(());
+        }
+
+        if ticks == 0 {
+            Err(SemaphoreError::WouldBlock)
+        } else {
+            self.smph.wait_cs(task, ticks, cs).map_err(|_| SemaphoreError::Timeout)
+        }
+    }
+
+    /// Gives a token back.
+    /// Returns an error if count is already at its maximum value.
+    pub fn give(&self) -> Result<(), SemaphoreError> {
+        let cs = CritSect::activate();
+        if self.smph.locked.raw() != 0 {
+            self.smph.release_cs(cs);
+            Ok(())
+        } else {
+            self.give_count()
+        }
+    }
+
+    /// Like give, but without a SysCall: woken is set if a Task has to preempt the running one, see yield_from_isr
+    pub fn give_from_isr(&self, woken: &mut bool) -> Result<(), SemaphoreError> {
+        let cs = CritSect::activate();
+        if self.smph.locked.raw() != 0 {
+            self.smph.release_from_isr_cs(&cs, woken);
+            Ok(())
+        } else {
+            self.give_count()
+        }
+    }
+
+    fn give_count(&self) -> Result<(), SemaphoreError> {
+        if self.count.get() < self.max {
+            self.count.set(self.count.get() + 1);
+            Ok(())
+        } else {
+            Err(SemaphoreError::Full)
+        }
+    }
+
+    fn take_count(&self) -> Result<(), SemaphoreError> {
+        match self.count.get() {
+            0 => Err(SemaphoreError::WouldBlock),
+            count => {
+                self.count.set(count - 1);
+                Ok(())
+            },
+        }
+    }
 }
 
+#[derive(Debug)]
 pub struct Rendezvous {
-    mask: AtomicBitVec,
+    tasks: &'static [&'static Task],
     arrived: AtomicBitVec,
 }
 
+unsafe impl Sync for Rendezvous {}
+
 impl Rendezvous {
-    pub const fn new(mask: u32) -> Self {
+    /// Tasks that have to meet: ids are given by add_task, so the mask is built when they meet.
+    /// Set of Tasks can't be modified, using more of a "const setup" logic, but Tasks not added
+    /// to the Kernel or deleted are not waited for.
+    pub const fn new(tasks: &'static [&'static Task]) -> Self {
         Self {
-            mask: AtomicBitVec::init(mask),
+            tasks,
             arrived: AtomicBitVec::new()
         }
     }
 
-    /// Functions should check which Tasks are waiting for a Rendezvous and unlock those
-    /// that where left off this new mask... Rigth now we simply don't allow to modify
-    /// the mask, using more of a "const setup" logic.
-    /// Maybe in future we should implement all required logic to handle a mask change.
-    /* pub */ fn set_mask(&self, mask: BitVec) {
-        let cs = CritSect::activate();
-        self.mask.write_raw(mask.raw());
-        cs.deactivate();
+    fn mask(&self) -> BitVec {
+        let mut mask = BitVec::new();
+        for task in self.tasks {
+            if task.id != IDLE_PRIO {
+                mask.set(task.id);
+            }
+        }
+        mask
     }
 
     pub fn meet(&self, task: &Task) {
-        SysCalls::meet_at_rendezvous(self, task.prio);
+        SysCalls::meet_at_rendezvous(self, task.id);
     }
 }
 
 
-pub struct Mutex<T> {
+/// Condition a Task waits for on an EventGroup
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventWait {
+    /// At least one of the flags in mask is set
+    Any,
+    /// All the flags in mask are set
+    All,
+}
+
+impl EventWait {
+    const fn satisfied(self, flags: BitVec, mask: BitVec) -> bool {
+        match self {
+            EventWait::Any => flags.intersects(&mask),
+            EventWait::All => flags.superset_of(&mask),
+        }
+    }
+}
+
+/// Errors returned by EventGroup APIs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventError {
+    /// Flags don't satisfy the wait
+    WouldBlock,
+    /// Flags have not satisfied the wait before timeout expiration
+    Timeout,
+}
+
+/// Set of flags Tasks can wait for, in any or all combination, while ISRs and other Tasks set them.
+/// Unlike a Rendezvous, every waiting Task has its own mask.
+#[derive(Debug)]
+pub struct EventGroup {
+    flags: AtomicBitVec,
+    waiting: AtomicBitVec,
+}
+
+impl Default for EventGroup {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventGroup {
+    pub const fn new() -> Self {
+        Self {
+            flags: AtomicBitVec::new(),
+            waiting: AtomicBitVec::new(),
+        }
+    }
+
+    pub fn get(&self) -> BitVec {
+        BitVec::init(self.flags.raw())
+    }
+
+    /// Sets flags and wakes up Tasks whose wait is satisfied
+    pub fn set(&self, mask: BitVec) {
+        let cs = CritSect::activate();
+        self.flags.write_raw((self.get() | mask).raw());
+
+        if self.waiting.raw() != 0 {
+            SysCalls::set_events(self, cs);
+        }
+    }
+
+    /// Like set, but without a SysCall: woken is set if a Task has to preempt the running one, see yield_from_isr
+    pub fn set_from_isr(&self, mask: BitVec, woken: &mut bool) {
+        let cs = CritSect::activate();
+        self.flags.write_raw((self.get() | mask).raw());
+
+        let k = KERNEL.access(&cs);
+        for id in &self.wake_waiters(&mut k.tasks) {
+            *woken |= k.preempts(id);
+        }
+    }
+
+    /// All waiting Tasks are checked against the same flags, then flags are cleared at once.
+    /// Returns the Tasks woken up.
+    fn wake_waiters(&self, tasks: &mut TaskList) -> BitVec {
+        let flags = self.get();
+        let mut cleared = BitVec::new();
+        let mut woken = BitVec::new();
+        for id in BitVec::init(self.waiting.raw()).into_iter() {
+            if let Some(clear) = tasks.wake_on_events(id, flags) {
+                self.waiting.clear(id);
+                cleared |= clear;
+                woken.set(id);
+            }
+        }
+        self.flags.write_raw(cleared.difference(&flags).raw());
+
+        woken
+    }
+
+    pub fn clear(&self, mask: BitVec) {
+        let _cs = CritSect::activate();
+        self.flags.write_raw(mask.difference(&self.get()).raw());
+    }
+
+    /// Waits till flags in mask satisfy the condition, then returns the matched flags.
+    /// If clear is true, matched flags are cleared before returning.
+    pub fn wait(&self, task: &mut Task, mask: BitVec, wait: EventWait, clear: bool) -> BitVec {
+        let cs = CritSect::activate();
+        match self.check(mask, wait, clear) {
+            Some(matched) => matched,
+            // Without timeout the Task can only be woken up by a set
+            None => self.wait_cs(task, mask, wait, clear, 0, cs).unwrap_or(BitVec::new()),
+        }
+    }
+
+    pub fn wait_timeout(&self, task: &mut Task, mask: BitVec, wait: EventWait, clear: bool, timeout: ms) -> Result<BitVec, EventError> {
+        let ticks: Ticks = timeout.into();
+        let cs = CritSect::activate();
+        if let Some(matched) = self.check(mask, wait, clear) {
+            return Ok(matched);
+        }
+
+        if ticks == 0 {
+            Err(EventError::WouldBlock)
+        } else {
+            self.wait_cs(task, mask, wait, clear, ticks, cs)
+        }
+    }
+
+    /// Must be called inside a critical section
+    fn check(&self, mask: BitVec, wait: EventWait, clear: bool) -> Option<BitVec> {
+        let flags = self.get();
+        if !wait.satisfied(flags, mask) {
+            return None;
+        }
+
+        if clear {
+            self.flags.write_raw(mask.difference(&flags).raw());
+        }
+        Some(flags & mask)
+    }
+
+    fn wait_cs(&self, task: &Task, mask: BitVec, wait: EventWait, clear: bool, ticks: Ticks, cs: CritSect) -> Result<BitVec, EventError> {
+        let waiter = KERNEL.access(&cs).tasks.get_ref_mut(task.id);
+        waiter.event_flags = mask;
+        waiter.event_wait = wait;
+        waiter.event_clear = clear;
+        SysCalls::wait_events(self, task.id, ticks, cs);
+
+        // Kernel drops the EventGroup reference if timeout expires
+        let cs = CritSect::activate();
+        let waiter = KERNEL.access(&cs).tasks.get_ref_mut(task.id);
+        match waiter.events.take() {
+            Some(_) => Ok(waiter.event_flags),
+            None => Err(EventError::Timeout),
+        }
+    }
+}
+
+
+/// Errors returned by Mutex APIs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MutexError {
+    /// Mutex is owned by another Task
+    WouldBlock,
+    /// Mutex has not been handed over before timeout expiration
+    Timeout,
+    /// Task is trying to release a Mutex it doesn't own
+    NotOwner,
+    /// Task is trying to lock a (non recursive) Mutex it already owns
+    Recursive,
+}
+
+/// Ownership state of a Mutex, independent from the protected resource type so that kernel can handle it.
+/// Tasks waiting for the Mutex are stopped, while the owner inherits the highest priority among them.
+#[derive(Debug)]
+struct MutexLock {
     locker: Cell<NullablePtr<Task>>,
+    waiting: AtomicBitVec,
+    /// Next Mutex owned by the same Task
+    next: Cell<NullablePtr<MutexLock>>,
+    /// Additional locks taken by the owner of a RecursiveMutex
+    count: Cell<usize>,
+}
+
+impl MutexLock {
+    const fn new() -> Self {
+        Self {
+            locker: Cell::new(NullablePtr::null()),
+            waiting: AtomicBitVec::new(),
+            next: Cell::new(NullablePtr::null()),
+            count: Cell::new(0),
+        }
+    }
+
+    fn owner(&self) -> Option<usize> {
+        let locker = unsafe { &*self.locker.as_ptr() };
+        locker.get().map(|task| task.id)
+    }
+
+    fn try_lock(&self, id: usize) -> Result<(), MutexError> {
+        let cs = CritSect::activate();
+        match self.owner() {
+            None => {
+                KERNEL.access(&cs).tasks.take_mutex(self, id);
+                Ok(())
+            },
+            Some(owner) if owner == id => Err(MutexError::Recursive),
+            Some(_) => Err(MutexError::WouldBlock),
+        }
+    }
+
+    /// Waits for the Mutex to be handed over, with a timeout if ticks are not 0
+    fn lock(&self, id: usize, ticks: Ticks) -> Result<(), MutexError> {
+        let cs = CritSect::activate();
+        match self.owner() {
+            None => {
+                KERNEL.access(&cs).tasks.take_mutex(self, id);
+                Ok(())
+            },
+            Some(owner) if owner == id => Err(MutexError::Recursive),
+            Some(_) => {
+                SysCalls::lock_mutex(self, id, ticks, cs);
+
+                if self.owner() == Some(id) {
+                    Ok(())
+                } else {
+                    Err(MutexError::Timeout)
+                }
+            },
+        }
+    }
+
+    fn unlock(&self, id: usize) -> Result<(), MutexError> {
+        let cs = CritSect::activate();
+        if self.owner() != Some(id) {
+            return Err(MutexError::NotOwner);
+        }
+
+        SysCalls::unlock_mutex(self, id, cs);
+        Ok(())
+    }
+}
+
+pub struct Mutex<T> {
+    lock: MutexLock,
     resource: UnsafeCell<T>,
-    sem: Semaphore,
 }
 
 unsafe impl<T> Sync for Mutex<T> {}
@@ -1188,31 +3192,145 @@ unsafe impl<T> Sync for Mutex<T> {}
 impl<T> Mutex<T> {
     pub const fn new(value: T) -> Self {
         Self {
-            locker: Cell::new(NullablePtr::null()),
+            lock: MutexLock::new(),
             resource: UnsafeCell::new(value),
-            sem: Semaphore::new(),
         }
     }
 
-    pub fn acquire(&self, task: &Task) -> &mut T {
-        let locker = unsafe { &*self.locker.as_ptr() };
-        if locker.is_non_null() {
-            self.sem.acquire(task);
+    /// If the Mutex is owned by another Task, this one is stopped till the Mutex is handed over to it.
+    /// Meanwhile the owner runs with this Task priority, if higher than its own.
+    pub fn lock(&self, task: &Task) -> Result<MutexGuard<'_, T>, MutexError> {
+        self.lock.lock(task.id, 0)?;
+        Ok(MutexGuard { mutex: self, owner: task.id })
+    }
+
+    /// Takes the Mutex only if it is free, without waiting
+    pub fn try_lock(&self, task: &Task) -> Result<MutexGuard<'_, T>, MutexError> {
+        self.lock.try_lock(task.id)?;
+        Ok(MutexGuard { mutex: self, owner: task.id })
+    }
+
+    /// Like lock, giving up if the Mutex is not handed over before timeout
+    pub fn lock_timeout(&self, task: &Task, timeout: ms) -> Result<MutexGuard<'_, T>, MutexError> {
+        let ticks: Ticks = timeout.into();
+        if ticks == 0 {
+            return self.try_lock(task);
         }
-        let cs = CritSect::activate();
-        self.locker.set(NullablePtr::new(task));
-        cs.deactivate();
-        unsafe { &mut *self.resource.get() }
+
+        self.lock.lock(task.id, ticks)?;
+        Ok(MutexGuard { mutex: self, owner: task.id })
     }
 
-    pub fn release(&self, task: &Task) {
-        let cs = CritSect::activate();
-        let locker = unsafe { &*self.locker.as_ptr() };
-        if let Some(locked) = locker.get() {
-            if locked.prio == task.prio {
-                self.locker.set(NullablePtr::null());
-                self.sem.release_cs(cs);
-            }
+    /// Like lock, but the Mutex must be given back with release
+    pub fn acquire(&self, task: &Task) -> Result<&mut T, MutexError> {
+        self.lock.lock(task.id, 0)?;
+        Ok(unsafe { &mut *self.resource.get() })
+    }
+
+    /// Hands the Mutex over to the highest priority waiting Task, and gives back to the owner
+    /// the priority inherited through this Mutex.
+    pub fn release(&self, task: &Task) -> Result<(), MutexError> {
+        self.lock.unlock(task.id)
+    }
+}
+
+/// Access to the resource of a locked Mutex. Mutex is released when the guard is dropped.
+pub struct MutexGuard<'a, T> {
+    mutex: &'a Mutex<T>,
+    owner: usize,
+}
+
+impl<T> Deref for MutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.mutex.resource.get() }
+    }
+}
+
+impl<T> DerefMut for MutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.mutex.resource.get() }
+    }
+}
+
+impl<T> Drop for MutexGuard<'_, T> {
+    fn drop(&mut self) {
+        // Guard exists only while its Task owns the Mutex
+        let _ = self.mutex.lock.unlock(self.owner);
+    }
+}
+
+
+/// Mutex that can be locked again by its owner, i.e. by layered drivers sharing a peripheral.
+/// It is released when all the guards are dropped. As more guards can be alive at the same time,
+/// they give a shared reference only: use Cells inside the resource to modify it.
+pub struct RecursiveMutex<T> {
+    lock: MutexLock,
+    resource: T,
+}
+
+unsafe impl<T> Sync for RecursiveMutex<T> {}
+
+impl<T> RecursiveMutex<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            lock: MutexLock::new(),
+            resource: value,
+        }
+    }
+
+    pub fn lock(&self, task: &Task) -> Result<RecursiveMutexGuard<'_, T>, MutexError> {
+        self.relock(self.lock.lock(task.id, 0), task)
+    }
+
+    pub fn try_lock(&self, task: &Task) -> Result<RecursiveMutexGuard<'_, T>, MutexError> {
+        self.relock(self.lock.try_lock(task.id), task)
+    }
+
+    pub fn lock_timeout(&self, task: &Task, timeout: ms) -> Result<RecursiveMutexGuard<'_, T>, MutexError> {
+        let ticks: Ticks = timeout.into();
+        if ticks == 0 {
+            return self.try_lock(task);
+        }
+
+        self.relock(self.lock.lock(task.id, ticks), task)
+    }
+
+    /// Only the owner can get here with a Recursive error, so the counter doesn't need a critical section
+    fn relock(&self, res: Result<(), MutexError>, task: &Task) -> Result<RecursiveMutexGuard<'_, T>, MutexError> {
+        match res {
+            Ok(()) => {},
+            Err(MutexError::Recursive) => self.lock.count.set(self.lock.count.get() + 1),
+            Err(err) => return Err(err),
+        }
+
+        Ok(RecursiveMutexGuard { mutex: self, owner: task.id })
+    }
+}
+
+/// Shared access to the resource of a locked RecursiveMutex, there is no DerefMut: nested guards of the owner
+/// are alive at the same time. Mutable state goes in Cells, i.e. `RecursiveMutex<Cell<u32>>` or a struct of Cells.
+pub struct RecursiveMutexGuard<'a, T> {
+    mutex: &'a RecursiveMutex<T>,
+    owner: usize,
+}
+
+impl<T> Deref for RecursiveMutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.mutex.resource
+    }
+}
+
+impl<T> Drop for RecursiveMutexGuard<'_, T> {
+    fn drop(&mut self) {
+        let count = self.mutex.lock.count.get();
+        if count > 0 {
+            self.mutex.lock.count.set(count - 1);
+        } else {
+            let _ = self.mutex.lock.unlock(self.owner);
         }
     }
 }
@@ -1278,119 +3396,124 @@ where
 
     /// Adds an element to Queue, waiting till space is available
     pub fn push(&self, task: &Task, data: T) {
-        while self.cnt.get() >= SIZE {
-            self.push.acquire(task);
-        }
-
-        let cs = CritSect::activate();
-        let mut end = self.head.get();
-        self.buff[end].set(MaybeUninit::new(data));
-        end += 1;
-        self.cnt.update(|x| x + 1);
-        #[cfg(feature = "buffers_watermark")]
-        self.watermark.update(|w| w.max(self.cnt.get()));
-        
-        if end >= SIZE {
-            end = 0;
-        }
-        self.head.set(end);
-
-        self.pop.release_cs(cs);
-    }
-
-    /// Adds an element to Queue; if there is no space available, waits for indicated timeout
-    pub fn push_timeout(&self, task: &mut Task, data: T, timeout: ms) -> Result<(), ()> {
-        while self.cnt.get() >= SIZE {
-            self.push.wait(task, timeout)?;
-        }
-
-        let cs = CritSect::activate();
-        let mut end = self.head.get();
-        self.buff[end].set(MaybeUninit::new(data));
-        end += 1;
-        self.cnt.update(|c| c + 1);
-        #[cfg(feature = "buffers_watermark")]
-        self.watermark.update(|w| w.max(self.cnt.get()));
-
-        if end >= SIZE {
-            end = 0;
+        loop {
+            let cs = CritSect::activate();
+            if self.cnt.get() < SIZE {
+                self.enqueue(data);
+                self.pop.release_cs(cs);
+                return;
+            }
+            self.push.acquire_cs(task, cs);
         }
-        self.head.set(end);
+    }
 
-        self.pop.release_cs(cs); 
-        Ok(())
+    /// Adds an element to Queue; if there is no space available, waits for indicated timeout
+    pub fn push_timeout(&self, task: &mut Task, data: T, timeout: ms) -> Result<(), ()> {
+        loop {
+            let cs = CritSect::activate();
+            if self.cnt.get() < SIZE {
+                self.enqueue(data);
+                self.pop.release_cs(cs);
+                return Ok(());
+            }
+            self.push.wait_cs(task, timeout.into(), cs)?;
+        }
     }
 
     /// Adds an element to Queue only if there is space available
     pub fn push_dropping(&self, data: T) -> Result<(), ()> {
+        let cs = CritSect::activate();
         if self.cnt.get() >= SIZE {
             return Err(());
         }
-        
-        let cs = CritSect::activate();
-        let mut end = self.head.get();
-        self.buff[end].set(MaybeUninit::new(data));
-        end += 1;
-        self.cnt.update(|c| c + 1);
-        #[cfg(feature = "buffers_watermark")]
-        self.watermark.update(|w| w.max(self.cnt.get()));
-
-        if end >= SIZE {
-            end = 0;
-        }
-        self.head.set(end);
 
+        self.enqueue(data);
         self.pop.release_cs(cs);
         Ok(())
     }
 
     /// Takes an element from Queue, waiting till an element is available
     pub fn pop(&self, task: &Task) -> T {
-        while self.cnt.get() == 0 {
-            self.pop.acquire(task);
-        }
-
-        let cs = CritSect::activate();
-        let mut start = self.tail.get();
-        let res = unsafe { self.buff[start].get().assume_init() };
-        start += 1;
-        if start >= SIZE {
-            start = 0;
+        loop {
+            let cs = CritSect::activate();
+            if self.cnt.get() != 0 {
+                let res = self.dequeue();
+                self.push.release_cs(cs);
+                return res;
+            }
+            self.pop.acquire_cs(task, cs);
         }
-        self.tail.set(start);
-        self.cnt.update(|c| c - 1);
-
-        self.push.release_cs(cs);
-        res
     }
 
     /// Takes an element from Queue; if there is no element available, waits for indicated timeout
     pub fn pop_timeout(&self, task: &mut Task, timeout: ms) -> Result<T, ()> {
-        while self.cnt.get() == 0 {
-            self.pop.wait(task, timeout)?;
+        loop {
+            let cs = CritSect::activate();
+            if self.cnt.get() != 0 {
+                let res = self.dequeue();
+                self.push.release_cs(cs);
+                return Ok(res);
+            }
+            self.pop.wait_cs(task, timeout.into(), cs)?;
         }
+    }
 
+    /// Takes an element from Queue only if there is an element available
+    pub fn pop_available(&self) -> Option<T> {
         let cs = CritSect::activate();
-        let mut start = self.tail.get();
-        let res = unsafe { self.buff[start].get().assume_init() };
-        start += 1;
-        if start >= SIZE {
-            start = 0;
+        if self.cnt.get() == 0 {
+            return None;
         }
-        self.tail.set(start);
-        self.cnt.update(|c| c - 1);
 
+        let res = self.dequeue();
         self.push.release_cs(cs);
-        Ok(res)
+        Some(res)
     }
 
-    /// Takes an element from Queue only if there is an element available
-    pub fn pop_available(&self) -> Option<T> {
+    /// Adds an element to Queue only if there is space available.
+    /// Like push_dropping, but without a SysCall: woken is set if a Task has to preempt the running one, see yield_from_isr
+    #[allow(clippy::result_unit_err)] // Same error as push_dropping: Queue is full
+    pub fn push_from_isr(&self, data: T, woken: &mut bool) -> Result<(), ()> {
+        let cs = CritSect::activate();
+        if self.cnt.get() >= SIZE {
+            return Err(());
+        }
+
+        self.enqueue(data);
+        self.pop.release_from_isr_cs(&cs, woken);
+        Ok(())
+    }
+
+    /// Takes an element from Queue only if there is an element available.
+    /// Like pop_available, but without a SysCall: woken is set if a Task has to preempt the running one, see yield_from_isr
+    pub fn pop_from_isr(&self, woken: &mut bool) -> Option<T> {
+        let cs = CritSect::activate();
         if self.cnt.get() == 0 {
             return None;
         }
 
-        let cs = CritSect::activate();
+        let res = self.dequeue();
+        self.push.release_from_isr_cs(&cs, woken);
+        Some(res)
+    }
+
+    /// Must be called inside a critical section, with space available
+    fn enqueue(&self, data: T) {
+        let mut end = self.head.get();
+        self.buff[end].set(MaybeUninit::new(data));
+        end += 1;
+        self.cnt.update(|c| c + 1);
+        #[cfg(feature = "buffers_watermark")]
+        self.watermark.update(|w| w.max(self.cnt.get()));
+
+        if end >= SIZE {
+            end = 0;
+        }
+        self.head.set(end);
+    }
+
+    /// Must be called inside a critical section, with an element available
+    fn dequeue(&self) -> T {
         let mut start = self.tail.get();
         let res = unsafe { self.buff[start].get().assume_init() };
         start += 1;
@@ -1399,9 +3522,7 @@ where
         }
         self.tail.set(start);
         self.cnt.update(|c| c - 1);
-
-        self.push.release_cs(cs);
-        Some(res)
+        res
     }
 
     /// Get queued element count
@@ -1435,7 +3556,7 @@ where
 /// NOTE: Remember that data is passed by-copy, and **NOT** by reference!!!
 /// 
 /// Stream Buffers are useful when receiving data from UARTs, SPIs, I2Cs...
-/// You can add data from an ISR using '''write_dropping()''' function,
+/// You can add data from an ISR using '''write_from_isr()''' function,
 /// and you can get those data from the task with any "read" method you like.
 pub struct StreamBuffer<T: Sized + Copy, const SIZE: usize, const TRG: usize> {
     write: Semaphore,
@@ -1497,50 +3618,19 @@ where
     /// When number of elements written to the Stream Buffer is greater than TRG (trigger),
     /// a blocked reading task will be unlocked.
     pub fn write(&self, task: &Task, slice: &[T]) {
-        let mut space;
-        let mut to_write: usize = slice.len();
-
-        while to_write != 0  {
-            // Checks if same space is available
-            while {
-                space = SIZE - self.cnt.get();
-                space == 0
-            }  
-            {
-                self.write.acquire(task);
-            }
+        let mut written = 0;
 
+        while written < slice.len() {
             let cs = CritSect::activate();
-            let writable = usize::min(space, to_write);
-            let write = (SIZE - self.head.get()).min(writable);
-            let wrapping = writable - write;
-
-            for id in 0..write {
-                self.buff[id + self.head.get()].set(MaybeUninit::new(slice[id]));
-            }
-            
-            if wrapping != 0 {
-                for id in 0..wrapping {
-                    self.buff[id].set(MaybeUninit::new(slice[id + write]));
-                }
-                self.head.set(wrapping);
-            } else {
-                self.head.update(|h| h + writable);
+            if self.cnt.get() == SIZE {
+                self.write.acquire_cs(task, cs);
+                continue;
             }
-            self.cnt.update(|c| c + writable);
-            #[cfg(feature = "buffers_watermark")]
-            self.watermark.update(|w| w.max(self.cnt.get()));
 
-            // Updates total written with actual writable bytes.
-            // Maybe [slice] is bigger than [space], so we must 
+            // Maybe [slice] is bigger than free space, so we must
             // start all over again to reach slice's end
-            to_write -= writable;
-
-            if self.cnt.get() >= TRG {
-                self.read.release_cs(cs);
-            } else {
-                cs.deactivate();
-            }
+            written += self.write_available(&slice[written..]);
+            self.release_reader(cs);
         }
     }
 
@@ -1550,52 +3640,19 @@ where
     /// When number of elements written to the Stream Buffer is greater than TRG (trigger),
     /// a blocked reading task will be unlocked.
     pub fn write_timeout(&self, task: &mut Task, slice: &[T], timeout: ms) -> Result<(), usize> {
-        let mut space;
-        let mut to_write: usize = slice.len();
-
-        while to_write != 0  {
-            // Checks if same space is available
-            while {
-                space = SIZE - self.cnt.get();
-                space == 0
-            }  
-            {
-                if let Err(()) = self.write.wait(task, timeout) {
-                    return Err(slice.len() - to_write);
-                }
-            }
+        let mut written = 0;
 
+        while written < slice.len() {
             let cs = CritSect::activate();
-            let writable = usize::min(space, to_write);
-            let write = (SIZE - self.head.get()).min(writable);
-            let wrapping = writable - write;
-
-            for id in 0..write {
-                self.buff[id + self.head.get()].set(MaybeUninit::new(slice[id]));
-            }
-            
-            if wrapping != 0 {
-                for id in 0..wrapping {
-                    self.buff[id].set(MaybeUninit::new(slice[id + write]));
+            if self.cnt.get() == SIZE {
+                if let Err(()) = self.write.wait_cs(task, timeout.into(), cs) {
+                    return Err(written);
                 }
-                self.head.set(wrapping);
-            } else {
-                self.head.update(|h| h + writable);
+                continue;
             }
-            self.cnt.update(|c| c + writable);
-            #[cfg(feature = "buffers_watermark")]
-            self.watermark.update(|w| w.max(self.cnt.get()));
-
-            // Updates total written with actual writable bytes.
-            // Maybe [slice] is bigger than [space], so we must 
-            // start all over again to reach slice's end
-            to_write -= writable;
 
-            if self.cnt.get() >= TRG {
-                self.read.release_cs(cs);
-            } else {
-                cs.deactivate();
-            }
+            written += self.write_available(&slice[written..]);
+            self.release_reader(cs);
         }
         Ok(())
     }
@@ -1604,15 +3661,55 @@ where
     /// When number of elements written to the Stream Buffer is greater than TRG (trigger),
     /// a blocked reading task will be unlocked.
     pub fn write_dropping(&self, slice: &[T]) -> Result<usize, usize> {
-        let space = SIZE - self.cnt.get();
+        let cs = CritSect::activate();
 
         // No space available, just returns
-        if space == 0 {
+        if self.cnt.get() == SIZE {
             return Err(0);
         }
-        
+
+        let writable = self.write_available(slice);
+        self.release_reader(cs);
+
+        if writable == slice.len() {
+            Ok(writable)
+        } else {
+            Err(writable)
+        }
+    }
+
+    /// Wakes up the reading Task once the trigger level is reached
+    fn release_reader(&self, cs: CritSect) {
+        if self.cnt.get() >= TRG {
+            self.read.release_cs(cs);
+        } else {
+            cs.deactivate();
+        }
+    }
+
+    /// Writes only the elements that fit into free space available, like write_dropping, but without a SysCall:
+    /// woken is set if a Task has to preempt the running one, see yield_from_isr
+    pub fn write_from_isr(&self, slice: &[T], woken: &mut bool) -> Result<usize, usize> {
         let cs = CritSect::activate();
-        let writable = usize::min(space, slice.len());
+        if self.cnt.get() == SIZE {
+            return Err(0);
+        }
+
+        let writable = self.write_available(slice);
+        if self.cnt.get() >= TRG {
+            self.read.release_from_isr_cs(&cs, woken);
+        }
+
+        if writable == slice.len() {
+            Ok(writable)
+        } else {
+            Err(writable)
+        }
+    }
+
+    /// Must be called inside a critical section. Returns the number of elements written.
+    fn write_available(&self, slice: &[T]) -> usize {
+        let writable = usize::min(SIZE - self.cnt.get(), slice.len());
         let write = (SIZE - self.head.get()).min(writable);
         let wrapping = writable - write;
 
@@ -1633,51 +3730,23 @@ where
         #[cfg(feature = "buffers_watermark")]
         self.watermark.update(|w| w.max(self.cnt.get()));
 
-        if self.cnt.get() >= TRG {
-            self.read.release_cs(cs);
-        } else {
-            cs.deactivate();
-        }
-
-        if writable == slice.len() {
-            Ok(writable)
-        } else {
-            Err(writable)
-        }
+        writable
     }
 
     /// Reads requested number of elements from the Stream Buffer. It stops till 'slice' is full.
     /// It blocks when there are no elements to read.
     /// TRG has no effect in reading.
     pub fn read(&self, task: &Task, slice: &mut [T]) {
-        let mut to_read: usize = slice.len();
-
-        while to_read != 0  {
-            while self.cnt.get() == 0 {
-                self.read.acquire(task);
-            }
+        let mut read = 0;
 
+        while read < slice.len() {
             let cs = CritSect::activate();
-            let readable = usize::min(to_read, self.cnt.get());
-            let read = (SIZE - self.tail.get()).min(readable);
-            let remainder = readable - read;
-    
-            for id in 0..read {
-                slice[id] = unsafe { self.buff[id + self.tail.get()].get().assume_init() };
-            }
-            
-            if remainder != 0 {
-                for id in 0..remainder {
-                    slice[id + read] = unsafe { self.buff[id].get().assume_init() };
-                }
-                self.tail.set(remainder);
-            } else {
-                self.tail.set(self.tail.get() + readable);
+            if self.cnt.get() == 0 {
+                self.read.acquire_cs(task, cs);
+                continue;
             }
-    
-            self.cnt.set(self.cnt.get() - readable);
-            to_read -= readable;
 
+            read += self.read_cs(&mut slice[read..]);
             self.write.release_cs(cs);
         }
     }
@@ -1691,6 +3760,26 @@ where
             return 0;
         }
 
+        let readable = self.read_cs(slice);
+        self.write.release_cs(cs);
+        readable
+    }
+
+    /// Reads available number of elements, like read_available, but without a SysCall:
+    /// woken is set if a Task has to preempt the running one, see yield_from_isr
+    pub fn read_from_isr(&self, slice: &mut [T], woken: &mut bool) -> usize {
+        let cs = CritSect::activate();
+        if self.cnt.get() == 0 {
+            return 0;
+        }
+
+        let readable = self.read_cs(slice);
+        self.write.release_from_isr_cs(&cs, woken);
+        readable
+    }
+
+    /// Must be called inside a critical section. Returns the number of elements read.
+    fn read_cs(&self, slice: &mut [T]) -> usize {
         let readable = usize::min(slice.len(), self.cnt.get());
         let read = (SIZE - self.tail.get()).min(readable);
         let remainder = readable - read;
@@ -1709,8 +3798,6 @@ where
         }
 
         self.cnt.set(self.cnt.get() - readable);
-        
-        self.write.release_cs(cs);
         readable
     }
 
@@ -1718,39 +3805,20 @@ where
     /// It returns when 'slice' is full or when timeout has expired.
     /// TRG has no effect in reading.    
     pub fn read_timeout(&self, task: &mut Task, slice: &mut [T], timeout: ms) -> Result<(), usize> {
-        let mut to_read: usize = slice.len();
-
-        while to_read != 0  {
-            while self.cnt.get() == 0 {
-                if let Err(()) = self.read.wait(task, timeout) {
-                    return Err(slice.len() - to_read);
-                }
-            }
+        let mut read = 0;
 
+        while read < slice.len() {
             let cs = CritSect::activate();
-            let readable = usize::min(to_read, self.cnt.get());
-            let read = (SIZE - self.tail.get()).min(readable);
-            let remainder = readable - read;
-    
-            for id in 0..read {
-                slice[id] = unsafe { self.buff[id + self.tail.get()].get().assume_init() };
-            }
-            
-            if remainder != 0 {
-                for id in 0..remainder {
-                    slice[id + read] = unsafe { self.buff[id].get().assume_init() };
+            if self.cnt.get() == 0 {
+                if let Err(()) = self.read.wait_cs(task, timeout.into(), cs) {
+                    return Err(read);
                 }
-                self.tail.set(remainder);
-            } else {
-                self.tail.set(self.tail.get() + readable);
+                continue;
             }
-    
-            self.cnt.set(self.cnt.get() - readable);
-            to_read -= readable;
 
+            read += self.read_cs(&mut slice[read..]);
             self.write.release_cs(cs);
         }
-        
         Ok(())
     }
 
@@ -1776,4 +3844,187 @@ where
         self.cnt.set(0);
         cs.deactivate();
     }
-}
\ No newline at end of file
+}
+
+//*********************************************************************************************************************
+// MEMORY POOLS
+//*********************************************************************************************************************
+
+/// Bookkeeping of a MemPool, shared with its PoolBoxes
+struct PoolSlots {
+    used: AtomicBitVec,
+    free: Semaphore,
+}
+
+impl PoolSlots {
+    /// First free slot, marked as used
+    fn take(&self, size: usize) -> Option<usize> {
+        match self.used.raw().trailing_ones() as usize {
+            slot if slot < size => {
+                self.used.set(slot);
+                Some(slot)
+            }
+            _ => None,
+        }
+    }
+
+    fn give(&self, slot: usize) {
+        let cs = CritSect::activate();
+        self.used.clear(slot);
+        if self.free.locked.raw() != 0 {
+            self.free.release_cs(cs);
+        }
+    }
+
+    fn give_from_isr(&self, slot: usize, woken: &mut bool) {
+        let cs = CritSect::activate();
+        self.used.clear(slot);
+        self.free.release_from_isr_cs(&cs, woken);
+    }
+}
+
+/// Pool of N statically allocated blocks of T. Allocation and free are O(1), as free blocks are
+/// tracked by a bit vector; Tasks can wait for a block when the pool is exhausted.
+/// Blocks are handed out as PoolBoxes, that give the block back on drop: large frames can be
+/// passed between Tasks by moving the PoolBox (or its raw pointer through a Queue) instead of copying.
+pub struct MemPool<T: Sized, const N: usize> {
+    blocks: [UnsafeCell<MaybeUninit<T>>; N],
+    slots: PoolSlots,
+}
+
+unsafe impl<T: Sized + Send, const N: usize> Sync for MemPool<T, N> {}
+
+impl<T: Sized, const N: usize> Default for MemPool<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Sized, const N: usize> MemPool<T, N> {
+    pub const fn new() -> Self {
+        if N > BitVec::BITS {
+            panic!("Too many blocks in pool!");
+        }
+
+        Self {
+            blocks: [const { UnsafeCell::new(MaybeUninit::uninit()) }; N],
+            slots: PoolSlots { used: AtomicBitVec::new(), free: Semaphore::new() },
+        }
+    }
+
+    /// Number of free blocks
+    pub fn available(&self) -> usize {
+        N - BitVec::init(self.slots.used.raw()).count_ones()
+    }
+
+    fn boxed(&self, slot: usize, value: T) -> PoolBox<'_, T> {
+        let data = unsafe { (*self.blocks[slot].get()).write(value) };
+        PoolBox { data, slot, slots: &self.slots }
+    }
+
+    /// Takes a block only if available, without waiting. It can be called from ISRs too.
+    /// Value is given back if the pool is exhausted.
+    pub fn try_alloc(&self, value: T) -> Result<PoolBox<'_, T>, T> {
+        let cs = CritSect::activate();
+        let slot = self.slots.take(N);
+        cs.deactivate();
+
+        match slot {
+            Some(slot) => Ok(self.boxed(slot, value)),
+            None => Err(value),
+        }
+    }
+
+    /// Takes a block, waiting till one is freed
+    pub fn alloc(&self, task: &mut Task, value: T) -> PoolBox<'_, T> {
+        loop {
+            let cs = CritSect::activate();
+            if let Some(slot) = self.slots.take(N) {
+                cs.deactivate();
+                return self.boxed(slot, value);
+            }
+
+            // Without timeout the Task can only be woken up by a free
+            let _ = self.slots.free.wait_cs(task, 0, cs);
+        }
+    }
+
+    /// Takes a block; if the pool is exhausted, waits for indicated timeout.
+    /// Value is given back if timeout expires.
+    pub fn alloc_timeout(&self, task: &mut Task, value: T, timeout: ms) -> Result<PoolBox<'_, T>, T> {
+        let ticks: Ticks = timeout.into();
+        loop {
+            let cs = CritSect::activate();
+            if let Some(slot) = self.slots.take(N) {
+                cs.deactivate();
+                return Ok(self.boxed(slot, value));
+            }
+
+            if ticks == 0 || self.slots.free.wait_cs(task, ticks, cs).is_err() {
+                return Err(value);
+            }
+        }
+    }
+
+    /// Rebuilds a PoolBox from a pointer given by PoolBox::into_raw.
+    /// # Safety
+    /// Pointer must come from into_raw of a PoolBox of this pool, and it must be used only once.
+    pub unsafe fn from_raw(&self, ptr: *mut T) -> PoolBox<'_, T> {
+        let slot = ptr.offset_from(self.blocks[0].get() as *mut T) as usize;
+        PoolBox { data: &mut *ptr, slot, slots: &self.slots }
+    }
+}
+
+/// Block of a MemPool, given back to its pool when dropped.
+/// Dropped in an ISR it is given back like free_from_isr does, then the context switch is requested if needed:
+/// ISRs that free more blocks, or wake Tasks in other ways, should call free_from_isr and yield_from_isr once.
+pub struct PoolBox<'a, T: Sized> {
+    data: &'a mut T,
+    slot: usize,
+    slots: &'a PoolSlots,
+}
+
+impl<'a, T: Sized> PoolBox<'a, T> {
+    /// Gives the block back from an ISR: woken is set if a Task has to preempt the running one, see yield_from_isr
+    pub fn free_from_isr(self, woken: &mut bool) {
+        let this = core::mem::ManuallyDrop::new(self);
+        let data = unsafe { core::ptr::read(&this.data) as *mut T };
+        unsafe { core::ptr::drop_in_place(data) };
+        this.slots.give_from_isr(this.slot, woken);
+    }
+
+    /// Leaks the PoolBox into a raw pointer, to be sent to another Task (i.e. through a Queue).
+    /// The block stays allocated till MemPool::from_raw rebuilds the PoolBox.
+    pub fn into_raw(self) -> *mut T {
+        let this = core::mem::ManuallyDrop::new(self);
+        unsafe { core::ptr::read(&this.data) as *mut T }
+    }
+}
+
+impl<T: Sized> Deref for PoolBox<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.data
+    }
+}
+
+impl<T: Sized> DerefMut for PoolBox<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.data
+    }
+}
+
+impl<T: Sized> Drop for PoolBox<'_, T> {
+    fn drop(&mut self) {
+        unsafe { core::ptr::drop_in_place(self.data as *mut T) };
+        // A SysCall can't be made from an ISR
+        if Kernel::get_context().is_privileged() {
+            let mut woken = false;
+            self.slots.give_from_isr(self.slot, &mut woken);
+            yield_from_isr(woken);
+        } else {
+            self.slots.give(self.slot);
+        }
+    }
+}
@@ -16,6 +16,8 @@
 
 use core::arch::{asm, naked_asm};
 use core::marker::PhantomData;
+use core::fmt::{self, Display, Formatter};
+use core::mem::offset_of;
 
 use crate::hw::CPU_FREQUENCY;
 use crate::kernel::{SysCallArgs, SysCalls}; 
@@ -23,6 +25,8 @@ use crate::kernel::Task;
 use crate::kernel::{Kernel, KERNEL};
 use crate::kernel::CritSect;
 use crate::kernel::ExecContext;
+#[cfg(feature = "tickless")]
+use crate::kernel::Ticks;
 use crate::kernel::Vector;
 use crate::kernel::registers::*;
 
@@ -52,7 +56,114 @@ pub struct ExceptionFrame {
     reserved: u32,
 }
 
-/// TODO: use this to save Task context inside TCB
+impl ExceptionFrame {
+    pub fn pc(&self) -> usize {
+        self.pc as usize
+    }
+
+    pub fn lr(&self) -> usize {
+        self.lr as usize
+    }
+
+    pub fn status(&self) -> usize {
+        self.xpsr as usize
+    }
+}
+
+/// Fault status registers of the SCB, as read when the fault was taken
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FaultRegisters {
+    /// Configurable Fault Status: MemManage, BusFault and UsageFault causes
+    pub cfsr: usize,
+    /// HardFault Status
+    pub hfsr: usize,
+    /// MemManage Fault Address, valid if MMARVALID
+    pub mmfar: usize,
+    /// BusFault Address, valid if BFARVALID
+    pub bfar: usize,
+}
+
+impl FaultRegisters {
+    const CFSR_CAUSES: [(usize, &'static str); 18] = [
+        (1 << 0, "IACCVIOL"),
+        (1 << 1, "DACCVIOL"),
+        (1 << 3, "MUNSTKERR"),
+        (1 << 4, "MSTKERR"),
+        (1 << 5, "MLSPERR"),
+        (1 << 8, "IBUSERR"),
+        (1 << 9, "PRECISERR"),
+        (1 << 10, "IMPRECISERR"),
+        (1 << 11, "UNSTKERR"),
+        (1 << 12, "STKERR"),
+        (1 << 13, "LSPERR"),
+        (1 << 16, "UNDEFINSTR"),
+        (1 << 17, "INVSTATE"),
+        (1 << 18, "INVPC"),
+        (1 << 19, "NOCP"),
+        (1 << 20, "STKOF"),
+        (1 << 24, "UNALIGNED"),
+        (1 << 25, "DIVBYZERO"),
+    ];
+
+    const HFSR_CAUSES: [(usize, &'static str); 3] = [
+        (1 << 1, "VECTTBL"),
+        (1 << 30, "FORCED"),
+        (1 << 31, "DEBUGEVT"),
+    ];
+
+    /// Reads the fault status, then clears it so that next fault is decoded alone.
+    /// ARMv6-M has no fault status registers.
+    pub(crate) fn read() -> Self {
+        #[cfg(armv6m)]
+        return Self::default();
+
+        #[cfg(not(armv6m))]
+        {
+            let scb = SCB::new();
+            let regs = Self {
+                cfsr: scb.cfsr.read(),
+                hfsr: scb.hfsr.read(),
+                mmfar: scb.mmar.read(),
+                bfar: scb.bfar.read(),
+            };
+            scb.cfsr.write(regs.cfsr);
+            scb.hfsr.write(regs.hfsr);
+            regs
+        }
+    }
+
+    /// True if a MemManage fault has been raised by the stack guard of the Task
+    #[cfg(feature = "mpu_enabled")]
+    pub(crate) fn stack_fault(&self, mpu: &MpuContext) -> bool {
+        let mmfsr = self.cfsr & SCB::CFSR_MMFSR;
+        mmfsr & SCB::MMFSR_MSTKERR != 0 || (mmfsr & SCB::MMFSR_MMARVALID != 0 && mpu.guarded(self.mmfar))
+    }
+
+    /// Names of the fault causes that are set
+    pub fn causes(&self) -> impl Iterator<Item = &'static str> + '_ {
+        let cfsr = Self::CFSR_CAUSES.iter().filter(|(bit, _)| self.cfsr & bit != 0);
+        let hfsr = Self::HFSR_CAUSES.iter().filter(|(bit, _)| self.hfsr & bit != 0);
+        cfsr.chain(hfsr).map(|(_, name)| *name)
+    }
+}
+
+impl Display for FaultRegisters {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "CFSR={:#010x} HFSR={:#010x}", self.cfsr, self.hfsr)?;
+        for cause in self.causes() {
+            write!(f, " {}", cause)?;
+        }
+        if self.cfsr & SCB::MMFSR_MMARVALID != 0 {
+            write!(f, " MMFAR={:#010x}", self.mmfar)?;
+        }
+        if self.cfsr & SCB::BFSR_BFARVALID != 0 {
+            write!(f, " BFAR={:#010x}", self.bfar)?;
+        }
+        Ok(())
+    }
+}
+
+/// Task context saved inside TCB by PendSV, along with the EXC_RETURN used to resume the Task
 #[derive(Debug)]
 #[repr(C)]
 pub struct CpuContext {
@@ -65,11 +176,16 @@ pub struct CpuContext {
     r10: usize,
     r11: usize,
     sp: usize,
+    /// Bit 4 cleared when the Task has used the FPU and its frame holds s0-s15 and FPSCR
+    exc_return: usize,
     #[cfg(armv8m)]
     psplim: usize,
 }
 
 impl CpuContext {
+    /// Back to thread mode using PSP, basic frame
+    const EXC_RETURN: usize = 0xFFFFFFFD;
+
     pub const fn new() -> Self {
         Self {
             r4: 4,
@@ -81,44 +197,12 @@ impl CpuContext {
             r10: 10,
             r11: 11,
             sp : 0,
+            exc_return: Self::EXC_RETURN,
             #[cfg(armv8m)]
             psplim: 0,
         }
     }
 
-    #[inline(always)]
-    pub(crate) unsafe fn save(&self) {
-        #[cfg(armv6m)]
-        asm!(
-            "stm    {0}, {{r4-r7}}",
-            "mov    r4, r8",
-            "mov    r5, r9",
-            "mov    r6, r10",
-            "mov    r7, r11",
-            "stm    {1}, {{r4-r7}}",
-            "mrs    r1, PSP",
-            "str    r1, [{2}]",
-            in(reg) &self.r4,
-            in(reg) &self.r8,
-            in(reg) &self.sp,
-        );
-        #[cfg(not(armv6m))]
-        asm!(
-            "mrs    r12, PSP",
-            "stm    {0}, {{r4-r12}}",
-            in(reg) &self.r4,
-        );
-    }
-
-    #[inline(always)]
-    pub(crate) unsafe fn save_psp(&self) {
-        asm!(
-            "mrs    r1, PSP",
-            "str    r1, [{0}]",
-            in(reg) &self.sp,
-        );
-    }
-
     #[inline(always)]
     pub(crate) unsafe fn load(&self) {
         #[cfg(armv6m)]
@@ -163,8 +247,12 @@ impl CpuContext {
     }
 }
 
+/// FPU callee-saved registers, saved by PendSV only for Tasks that have used the FPU.
+/// s0-s15 and FPSCR are stacked lazily by the core in the exception frame.
 #[cfg(has_fpu)]
 #[cfg(feature = "fpu_enabled")]
+#[derive(Debug)]
+#[repr(C)]
 pub struct FpuContext {
     s16: u32,
     s17: u32,
@@ -209,9 +297,136 @@ impl FpuContext {
     }
 }
 
+/// Access rights of an MPU region, as seen by the running Task.
+/// Tasks run privileged (critical sections disable interrupts) over the default memory map,
+/// thus regions restrict the access to memory rather than granting it: Tasks are not isolated,
+/// only memory covered by a region is protected.
+#[cfg(feature = "mpu_enabled")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MpuAccess {
+    NoAccess = 0b000,
+    ReadOnly = 0b110,
+    ReadWrite = 0b011,
+}
+
+/// Memory type of an MPU region, as TEX, S, C and B bits of RASR
 #[cfg(feature = "mpu_enabled")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MpuMemory {
+    /// RAM and flash: shareable, write-through (TEX = 000, S = 1, C = 1, B = 0)
+    Normal = (1 << 18) | (1 << 17),
+    /// Peripheral registers: shareable device, writes can be buffered (TEX = 000, S = 1, C = 0, B = 1)
+    Device = (1 << 18) | (1 << 16),
+    /// Peripheral registers that need every access to complete before the next one (TEX = 000, C = 0, B = 0)
+    StronglyOrdered = 0,
+}
+
+/// Errors returned by Task MPU regions APIs
+#[cfg(feature = "mpu_enabled")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MpuError {
+    /// Task has not been added to the Kernel
+    NotAdded,
+    /// Region index is not one of the Task regions
+    BadIndex,
+    /// Size is not a power of two of at least 32 bytes, or base is not aligned to it
+    BadSize,
+}
+
+/// Region 0 is the null-pointer guard, then come the regions declared by the Task and its stack guard
+#[cfg(feature = "mpu_enabled")]
+const MPU_TASK_REGION: usize = 1;
+#[cfg(feature = "mpu_enabled")]
+pub(crate) const MPU_TASK_REGIONS: usize = 3;
+#[cfg(feature = "mpu_enabled")]
+const MPU_GUARD_SIZE: usize = 32;
+
+/// MPU regions of a Task, programmed at context switch. Last one is the stack guard.
+#[cfg(feature = "mpu_enabled")]
+#[derive(Debug)]
 pub struct MpuContext {
-    s0: u32,
+    rbar: [usize; MPU_TASK_REGIONS + 1],
+    rasr: [usize; MPU_TASK_REGIONS + 1],
+}
+
+#[cfg(feature = "mpu_enabled")]
+impl MpuContext {
+    pub const fn new() -> Self {
+        Self {
+            rbar: [0; MPU_TASK_REGIONS + 1],
+            rasr: [0; MPU_TASK_REGIONS + 1],
+        }
+    }
+
+    /// Size must be a power of two of at least 32 bytes, and base must be aligned to it
+    fn region(base: usize, size: usize, access: MpuAccess, memory: MpuMemory) -> Result<(usize, usize), MpuError> {
+        if size < 32 || !size.is_power_of_two() || base & (size - 1) != 0 {
+            return Err(MpuError::BadSize);
+        }
+
+        let rasr = MPU::RASR_XN
+            | (access as usize) << MPU::RASR_AP_POS
+            | memory as usize
+            | (size.trailing_zeros() as usize - 1) << MPU::RASR_SIZE_POS
+            | MPU::RASR_ENABLE;
+
+        Ok((base, rasr))
+    }
+
+    pub(crate) fn set_region(&mut self, index: usize, base: usize, size: usize, access: MpuAccess, memory: MpuMemory) -> Result<(), MpuError> {
+        if index >= MPU_TASK_REGIONS {
+            return Err(MpuError::BadIndex);
+        }
+
+        (self.rbar[index], self.rasr[index]) = Self::region(base, size, access, memory)?;
+        Ok(())
+    }
+
+    pub(crate) fn clear_region(&mut self, index: usize) -> Result<(), MpuError> {
+        if index >= MPU_TASK_REGIONS {
+            return Err(MpuError::BadIndex);
+        }
+
+        self.rbar[index] = 0;
+        self.rasr[index] = 0;
+        Ok(())
+    }
+
+    /// Read-only region at the bottom of the stack: pushing into it traps into OSMemoryFault
+    /// before neighbouring memory is corrupted.
+    fn set_guard(&mut self, stack: *const [usize]) {
+        let start = stack as *const usize as usize;
+        let guard = (start + MPU_GUARD_SIZE - 1) & !(MPU_GUARD_SIZE - 1);
+
+        if let Ok(region) = Self::region(guard, MPU_GUARD_SIZE, MpuAccess::ReadOnly, MpuMemory::Normal) {
+            (self.rbar[MPU_TASK_REGIONS], self.rasr[MPU_TASK_REGIONS]) = region;
+        }
+    }
+
+    pub(crate) fn guarded(&self, address: usize) -> bool {
+        let guard = self.rbar[MPU_TASK_REGIONS];
+        self.rasr[MPU_TASK_REGIONS] & MPU::RASR_ENABLE != 0 && address >= guard && address < guard + MPU_GUARD_SIZE
+    }
+
+    /// Programs the regions of the Task that is going to run
+    pub(crate) fn load(&self) {
+        let mpu = MPU::new();
+        for id in 0..=MPU_TASK_REGIONS {
+            mpu.rbar.write(self.rbar[id] | MPU::RBAR_VALID | (MPU_TASK_REGION + id));
+            mpu.rasr.write(self.rasr[id]);
+        }
+        MPU::sync();
+    }
+
+    /// Disables Task regions, i.e. to paint again a stack whose guard is active
+    pub(crate) fn unload() {
+        let mpu = MPU::new();
+        for id in 0..=MPU_TASK_REGIONS {
+            mpu.rbar.write(MPU::RBAR_VALID | (MPU_TASK_REGION + id));
+            mpu.rasr.write(0);
+        }
+        MPU::sync();
+    }
 }
 
 
@@ -369,6 +584,29 @@ unsafe extern "C" fn __ENTRY() {
         "3:",
         "cmp    r2, r4",
         "bcc    2b",
+        /* Full access to CP10 and CP11 in CPACR, automatic and lazy FPU stacking in FPCCR */
+        #[cfg(all(has_fpu, feature = "fpu_enabled"))]
+        "ldr    r0, =0xE000ED88",
+        #[cfg(all(has_fpu, feature = "fpu_enabled"))]
+        "ldr    r1, [r0]",
+        #[cfg(all(has_fpu, feature = "fpu_enabled"))]
+        "orr    r1, r1, #0x00F00000",
+        #[cfg(all(has_fpu, feature = "fpu_enabled"))]
+        "str    r1, [r0]",
+        #[cfg(all(has_fpu, feature = "fpu_enabled"))]
+        "ldr    r0, =0xE000EF34",
+        #[cfg(all(has_fpu, feature = "fpu_enabled"))]
+        "ldr    r1, [r0]",
+        #[cfg(all(has_fpu, feature = "fpu_enabled"))]
+        "orr    r1, r1, #0xC0000000",
+        #[cfg(all(has_fpu, feature = "fpu_enabled"))]
+        "str    r1, [r0]",
+        #[cfg(all(has_fpu, feature = "fpu_enabled"))]
+        "dsb",
+        #[cfg(all(has_fpu, feature = "fpu_enabled"))]
+        "isb",
+        /* Latch the crash log of the previous run */
+        "bl     OSBoot",
         /* Set the stack and call the application's entry point.*/
         "b	    OSEntry",
     );
@@ -388,11 +626,23 @@ extern "C" fn NonMaskableInt() {
 unsafe extern "C" fn HardFault() {
     naked_asm!(
         // Ottiene la &TCB running
-        "ldr    r3, =KERNEL",
-        "ldr    r1, [r3, #0]",
+        "ldr    r3, =KERNEL+{running}",
+        "ldr    r1, [r3]",
+        // Frame sullo stack in uso prima dell'eccezione (EXC_RETURN bit 2)
+        "mov    r2, lr",
+        "movs   r3, #4",
+        "tst    r2, r3",
+        "beq    1f",
         "mrs    r0, PSP",
+        "movs   r2, #0",
+        "b      OSHardFault",
+        "1:",
+        "mrs    r0, MSP",
+        // Frame sullo stack principale: errore in handler mode (terzo argomento)
+        "movs   r2, #1",
         // Gestione dell'errore da parte di Rust
         "b      OSHardFault",
+        running = const offset_of!(Kernel, running),
     );
 }
 
@@ -403,11 +653,23 @@ unsafe extern "C" fn HardFault() {
 unsafe extern "C" fn MemoryManagement() {
     naked_asm!(
         // Ottiene la &TCB running
-        "ldr    r3, =KERNEL",
-        "ldr    r1, [r3, #0]",
+        "ldr    r3, =KERNEL+{running}",
+        "ldr    r1, [r3]",
+        // Frame sullo stack in uso prima dell'eccezione (EXC_RETURN bit 2)
+        "mov    r2, lr",
+        "movs   r3, #4",
+        "tst    r2, r3",
+        "beq    1f",
         "mrs    r0, PSP",
+        "movs   r2, #0",
+        "b      OSMemoryFault",
+        "1:",
+        "mrs    r0, MSP",
+        // Frame sullo stack principale: errore in handler mode (terzo argomento)
+        "movs   r2, #1",
         // Gestione dell'errore da parte di Rust
         "b      OSMemoryFault",
+        running = const offset_of!(Kernel, running),
     );
 }
 
@@ -418,11 +680,23 @@ unsafe extern "C" fn MemoryManagement() {
 unsafe extern "C" fn BusFault() {
     naked_asm!(
         // Ottiene la &TCB running
-        "ldr    r3, =KERNEL",
-        "ldr    r1, [r3, #0]",
+        "ldr    r3, =KERNEL+{running}",
+        "ldr    r1, [r3]",
+        // Frame sullo stack in uso prima dell'eccezione (EXC_RETURN bit 2)
+        "mov    r2, lr",
+        "movs   r3, #4",
+        "tst    r2, r3",
+        "beq    1f",
         "mrs    r0, PSP",
+        "movs   r2, #0",
+        "b      OSBusFault",
+        "1:",
+        "mrs    r0, MSP",
+        // Frame sullo stack principale: errore in handler mode (terzo argomento)
+        "movs   r2, #1",
         // Gestione dell'errore da parte di Rust
         "b      OSBusFault",
+        running = const offset_of!(Kernel, running),
     );
 }
 
@@ -433,11 +707,23 @@ unsafe extern "C" fn BusFault() {
 unsafe extern "C" fn UsageFault() {
     naked_asm!(
         // Ottiene la &TCB running
-        "ldr    r3, =KERNEL",
-        "ldr    r1, [r3, #0]",
+        "ldr    r3, =KERNEL+{running}",
+        "ldr    r1, [r3]",
+        // Frame sullo stack in uso prima dell'eccezione (EXC_RETURN bit 2)
+        "mov    r2, lr",
+        "movs   r3, #4",
+        "tst    r2, r3",
+        "beq    1f",
         "mrs    r0, PSP",
+        "movs   r2, #0",
+        "b      OSUsageFault",
+        "1:",
+        "mrs    r0, MSP",
+        // Frame sullo stack principale: errore in handler mode (terzo argomento)
+        "movs   r2, #1",
         // Gestione dell'errore da parte di Rust
         "b      OSUsageFault",
+        running = const offset_of!(Kernel, running),
     );
 }
 
@@ -448,11 +734,23 @@ unsafe extern "C" fn UsageFault() {
 unsafe extern "C" fn SecureFault() {
     naked_asm!(
         // Ottiene la &TCB running
-        "ldr    r3, =KERNEL",
-        "ldr    r1, [r3, #0]",
+        "ldr    r3, =KERNEL+{running}",
+        "ldr    r1, [r3]",
+        // Frame sullo stack in uso prima dell'eccezione (EXC_RETURN bit 2)
+        "mov    r2, lr",
+        "movs   r3, #4",
+        "tst    r2, r3",
+        "beq    1f",
         "mrs    r0, PSP",
+        "movs   r2, #0",
+        "b      OSSecureFault",
+        "1:",
+        "mrs    r0, MSP",
+        // Frame sullo stack principale: errore in handler mode (terzo argomento)
+        "movs   r2, #1",
         // Gestione dell'errore da parte di Rust
         "b      OSSecureFault",
+        running = const offset_of!(Kernel, running),
     );
 }
 
@@ -466,7 +764,6 @@ extern "C" fn SVCall() {
     // Access Kernel without critical section, as we are already at max priority
     unsafe { 
         let k = KERNEL.access_unsafe();
-        //k.running_mut().context.save_psp();
         k.handle_syscall(syscall);
     };
 }
@@ -479,19 +776,121 @@ extern "C" fn DebugMonitor() {
     }
 }
 
+/// Saves the running Task context in its TCB, s16-s31 only if EXC_RETURN tells that the Task
+/// has used the FPU: the first FPU access from here makes the core stack s0-s15 and FPSCR in the
+/// space reserved for them in the exception frame (lazy stacking).
+/// Then the next Task context is loaded and the Task is resumed with its own EXC_RETURN.
 #[unsafe(naked)]
 #[no_mangle]
 #[allow(non_snake_case)]
 unsafe extern "C" fn PendSV() {
     naked_asm!(
         "cpsid	i",
+        "ldr    r3, =KERNEL+{running}", // Get &Scheduler.running
+        "ldr    r0, [r3]",
+
+        // Saving running Task context
+        #[cfg(armv6m)]
+        "ldr    r1, ={context}",      // Offset can exceed the 8 bits immediate of movs
+        #[cfg(armv6m)]
+        "adds   r1, r1, r0",
+        #[cfg(armv6m)]
+        "stmia  r1!, {{r4-r7}}",
+        #[cfg(armv6m)]
+        "mov    r4, r8",
+        #[cfg(armv6m)]
+        "mov    r5, r9",
+        #[cfg(armv6m)]
+        "mov    r6, r10",
+        #[cfg(armv6m)]
+        "mov    r7, r11",
+        #[cfg(armv6m)]
+        "stmia  r1!, {{r4-r7}}",
+        #[cfg(armv6m)]
+        "mrs    r2, PSP",
+        #[cfg(armv6m)]
+        "mov    r3, lr",
+        #[cfg(armv6m)]
+        "stmia  r1!, {{r2, r3}}",
+
+        #[cfg(not(armv6m))]
+        "add    r1, r0, #{context}",
+        #[cfg(not(armv6m))]
+        "mrs    r12, PSP",
+        #[cfg(not(armv6m))]
+        "stm    r1, {{r4-r12, lr}}",
+
+        #[cfg(all(has_fpu, feature = "fpu_enabled"))]
+        ".fpu   fpv4-sp-d16",
+        #[cfg(all(has_fpu, feature = "fpu_enabled"))]
+        "tst    lr, #0x10",
+        #[cfg(all(has_fpu, feature = "fpu_enabled"))]
+        "bne    1f",
+        #[cfg(all(has_fpu, feature = "fpu_enabled"))]
+        "add    r1, r0, #{fpu}",
+        #[cfg(all(has_fpu, feature = "fpu_enabled"))]
+        "vstm   r1, {{s16-s31}}",
+        "1:",
+
         "ldr    r0, =KERNEL",           // Get &Scheduler
+        "bl     switch_to_next",
+
+        // Loading next Task context
+        "ldr    r3, =KERNEL+{running}",
+        "ldr    r0, [r3]",
+
+        #[cfg(armv6m)]
+        "ldr    r1, ={context}+16",
+        #[cfg(armv6m)]
+        "adds   r1, r1, r0",
+        #[cfg(armv6m)]
+        "ldmia  r1!, {{r4-r7}}",
+        #[cfg(armv6m)]
+        "mov    r8, r4",
+        #[cfg(armv6m)]
+        "mov    r9, r5",
+        #[cfg(armv6m)]
+        "mov    r10, r6",
+        #[cfg(armv6m)]
+        "mov    r11, r7",
+        #[cfg(armv6m)]
+        "ldmia  r1!, {{r2, r3}}",
+        #[cfg(armv6m)]
+        "msr    PSP, r2",
+        #[cfg(armv6m)]
+        "mov    lr, r3",
+        #[cfg(armv6m)]
+        "subs   r1, r1, #40",
+        #[cfg(armv6m)]
+        "ldmia  r1!, {{r4-r7}}",
 
-        "bl      switch_to_next",
+        #[cfg(not(armv6m))]
+        "add    r1, r0, #{context}",
+        #[cfg(armv8m)]
+        "ldr    r2, [r1, #40]",
+        #[cfg(armv8m)]
+        "msr    PSPLIM, r2",
+        #[cfg(not(armv6m))]
+        "ldm    r1, {{r4-r12, lr}}",
+        #[cfg(not(armv6m))]
+        "msr    PSP, r12",
+
+        #[cfg(all(has_fpu, feature = "fpu_enabled"))]
+        "tst    lr, #0x10",
+        #[cfg(all(has_fpu, feature = "fpu_enabled"))]
+        "bne    2f",
+        #[cfg(all(has_fpu, feature = "fpu_enabled"))]
+        "add    r1, r0, #{fpu}",
+        #[cfg(all(has_fpu, feature = "fpu_enabled"))]
+        "vldm   r1, {{s16-s31}}",
+        "2:",
 
-        "ldr    lr, =0xFFFFFFFD",
         "cpsie	i",
         "bx     lr",
+        running = const offset_of!(Kernel, running),
+        context = const offset_of!(Task, context),
+        #[cfg(all(has_fpu, feature = "fpu_enabled"))]
+        fpu = const offset_of!(Task, fpu),
     );
 }
 
@@ -499,9 +898,14 @@ unsafe extern "C" fn PendSV() {
 #[allow(non_snake_case)]
 extern "C" fn SysTick() {
     let cs = CritSect::activate();
-    //unsafe { KERNEL.access(&cs).running_mut().context.save_psp() };
+    #[cfg(feature = "trace")]
+    crate::kernel::trace::isr_enter(Exceptions::SysTick as usize);
+
     KERNEL.access(&cs).inc_system_ticks();
     KERNEL.access(&cs).schedule_next();
+
+    #[cfg(feature = "trace")]
+    crate::kernel::trace::isr_exit(Exceptions::SysTick as usize);
     cs.deactivate();
 }
 
@@ -682,6 +1086,15 @@ impl SysCalls {
             SysCalls::MeetAtRendezvous => asm!("svc 5"),
             SysCalls::WaitSemaphore => asm!("svc 6"),
             SysCalls::ReleaseSemaphore => asm!("svc 7"),
+            SysCalls::LockMutex => asm!("svc 8"),
+            SysCalls::UnlockMutex => asm!("svc 9"),
+            SysCalls::WaitEvents => asm!("svc 10"),
+            SysCalls::SetEvents => asm!("svc 11"),
+            SysCalls::WaitNotification => asm!("svc 12"),
+            SysCalls::NotifyTask => asm!("svc 13"),
+            SysCalls::DeleteTask => asm!("svc 14"),
+            SysCalls::SuspendTask => asm!("svc 15"),
+            SysCalls::ResumeTask => asm!("svc 16"),
         }
     }
 }
@@ -721,6 +1134,9 @@ impl Kernel {
 
     #[inline(always)]
     pub(crate) fn start_task(task: &Task) -> ! {
+        #[cfg(feature = "mpu_enabled")]
+        task.mpu.load();
+
         unsafe {
             task.context.load();
             asm!(
@@ -738,16 +1154,28 @@ impl Kernel {
         self.core.scb.set_pendsv();
     }
 
+    /// Requests a system reset to the SCB and waits for it
+    pub(crate) fn system_reset() -> ! {
+        SCB::new().aircr.write(SCB::AIRCR_VECTKEY | SCB::AIRCR_SYSRESETREQ);
+        unsafe {
+            asm!("dsb");
+        }
+        loop {
+            Self::nop();
+        }
+    }
+
+    /// Handler mode, i.e. an ISR or the Kernel, is told by a non zero exception number in IPSR
     #[inline(always)]
     pub(crate) extern "C" fn get_context() -> ExecContext {
         let val: usize;
         unsafe {
             asm!(
-                "mrs    {out}, CONTROL",
+                "mrs    {out}, IPSR",
                 out = out(reg) val,
             );
-            val.into()
         }
+        if val == 0 { ExecContext::Process } else { ExecContext::Privileged }
     }
 }
 
@@ -759,7 +1187,7 @@ impl Task {
 
         stack[len - 01] = 1 << 24; // xPSR - Thumb state active
         stack[len - 02] = self.task as usize; // PC
-        stack[len - 03] = 0xFFFFFFFD; // LR
+        stack[len - 03] = CpuContext::EXC_RETURN; // LR
         stack[len - 04] = 0xC; // R12
         stack[len - 05] = 0x3; // R3
         stack[len - 06] = 0x2; // R2
@@ -767,12 +1195,16 @@ impl Task {
         stack[len - 08] = pointer as usize; // R0
 
         self.context.sp = (&stack[len - 08] as *const usize) as usize;
+        self.context.exc_return = CpuContext::EXC_RETURN;
         self.stack_start = (&stack[len - 01] as *const usize) as usize;
         
         #[cfg(armv8m)] 
         {
             self.context.psplim = self.stack;
         }
+
+        #[cfg(feature = "mpu_enabled")]
+        self.mpu.set_guard(self.stack);
     }
 }
 
@@ -789,7 +1221,8 @@ pub struct CorePeripherals {
     systick: SysTickTimer,
     nvic: NVIC,
     scb: SCB,
-    mpu: PhantomData<u32>,
+    mpu: MPU,
+    dwt: DWT,
     fpu: PhantomData<u32>,
 }
 
@@ -799,7 +1232,8 @@ impl CorePeripherals {
             systick: SysTickTimer::new(),
             nvic: NVIC::new(),
             scb: SCB::new(),
-            mpu: PhantomData,
+            mpu: MPU::new(),
+            dwt: DWT::new(),
             fpu: PhantomData,
         }
     }
@@ -837,9 +1271,82 @@ impl CorePeripherals {
         self.nvic.enable_interrupt(Exceptions::SysTick);
         self.nvic.set_interrupt_prio(Exceptions::SysTick, IntPrio::Pri14);
 
+        #[cfg(feature = "mpu_enabled")]
+        {
+            self.scb.shcrs.set(SCB::SHCSR_MEMFAULTENA);
+            self.mpu.init();
+        }
+
+        #[cfg(any(feature = "cpu_stats", feature = "trace"))]
+        self.dwt.start_cycles();
+
         self.systick.init();
     }
 
+    /// Free-running time base of CPU statistics and trace: core cycles counted by the DWT
+    #[cfg(any(feature = "cpu_stats", feature = "trace"))]
+    #[inline]
+    pub(crate) fn cycles() -> u32 {
+        DWT::new().cyccnt.read() as u32
+    }
+
+    #[cfg(any(feature = "cpu_stats", feature = "trace"))]
+    pub(crate) fn cycles_frequency() -> u32 {
+        let cpu: crate::kernel::Hz = CPU_FREQUENCY.into();
+        let cpu: usize = cpu.into();
+        cpu as u32
+    }
+
+    /// Sleeps with SysTick reprogrammed to fire after the given ticks, unless another interrupt comes first.
+    /// Returns the elapsed ticks that SysTick won't count.
+    #[cfg(feature = "tickless")]
+    pub(crate) fn suppress_ticks(&mut self, ticks: Ticks) -> Ticks {
+        let period = self.systick.period;
+        let ticks = ticks.min((SysTickTimer::MAX_RELOAD / period) as Ticks) as usize;
+        if ticks < 2 {
+            Kernel::core_sleep();
+            return 0;
+        }
+
+        self.systick.stop();
+        if self.systick.count_flag() {
+            // Tick elapsed while stopping: SysTick is pending already
+            self.systick.start();
+            return 0;
+        }
+
+        // Reload is shortened by cycles already elapsed in current tick
+        let current = self.systick.cvr.read();
+        self.systick.set_reload(current + (ticks - 1) * period - 1).zero_count().start();
+
+        Kernel::core_sleep();
+
+        self.systick.stop();
+        let skipped = if self.systick.count_flag() {
+            // Whole time elapsed: SysTick is pending and it will count last tick
+            self.systick.set_reload(period - 1).zero_count();
+            ticks - 1
+        } else {
+            // Woken up by another interrupt: SysTick must fire at next tick boundary.
+            // Boundaries still ahead are one every period from the end, the last one counted by SysTick.
+            let remaining = self.systick.cvr.read();
+            let ahead = remaining / period;
+            let (skipped, partial) = match remaining % period {
+                // A boundary is now: it is counted here, SysTick fires at the next one
+                0 => (ticks - ahead, period),
+                // Reload value must be at least 1: a boundary one cycle ahead is counted here as well
+                1 => (ticks - ahead, period + 1),
+                cycles => (ticks - 1 - ahead, cycles),
+            };
+            self.systick.set_reload(partial - 1).zero_count();
+            skipped
+        };
+
+        self.systick.start();
+        self.systick.set_reload(period - 1);
+        skipped as Ticks
+    }
+
     #[inline]
     pub fn sleep_on_exit(&self, sleep: bool) {
         self.scb.sleep_on_exit(sleep);
@@ -885,12 +1392,17 @@ struct SysTickTimer {
     rvr: RW<SYSTICK_ADR, 0x04>,
     cvr: RW<SYSTICK_ADR, 0x08>,
     calib: RW<SYSTICK_ADR, 0x0C>,
+
+    /// Core clock cycles in a tick
+    period: usize,
 }
 
 impl SysTickTimer {
     const ENABLE: usize = 1;
     const TICKINT: usize = 1 << 1;
     const CLKSOURCE: usize = 1 << 2;
+    const COUNTFLAG: usize = 1 << 16;
+    const MAX_RELOAD: usize = 0x00FF_FFFF;
     //const SKEW: usize = 1 << 30;
     const TENMS_MASK: usize = 0x00FF_FFFF;
 
@@ -900,6 +1412,7 @@ impl SysTickTimer {
             rvr: RW::new(),
             cvr: RW::new(), 
             calib: RW::new(),
+            period: 0,
          }
     }
 
@@ -927,6 +1440,7 @@ impl SysTickTimer {
         let cpu: crate::kernel::Hz = CPU_FREQUENCY.into();
         let cpu: usize = cpu.into();
         let reload = cpu / 1000;
+        self.period = reload + 1;
         
         self.set_reload(reload).zero_count();
         self.set_clocksource(ClockSource::CoreClock)
@@ -934,6 +1448,11 @@ impl SysTickTimer {
             .start();
     }
 
+    /// Counter reached zero since last read. Flag is cleared by reading
+    fn count_flag(&self) -> bool {
+        self.crs.read() & Self::COUNTFLAG != 0
+    }
+
     fn zero_count(&mut self) -> &mut Self {
         self.cvr.write(0);
         self
@@ -1032,6 +1551,13 @@ struct SCB {
 impl SCB {
     const ICSR_PENDSVSET_MASK: usize = 1 << 28;
     const SCR_SLEEPONEXIT: usize = 1;
+    const SHCSR_MEMFAULTENA: usize = 1 << 16;
+    const CFSR_MMFSR: usize = 0xFF;
+    const MMFSR_MSTKERR: usize = 1 << 4;
+    const MMFSR_MMARVALID: usize = 1 << 7;
+    const BFSR_BFARVALID: usize = 1 << 15;
+    const AIRCR_VECTKEY: usize = 0x05FA << 16;
+    const AIRCR_SYSRESETREQ: usize = 1 << 2;
 
     const fn new() -> Self {
         Self {
@@ -1066,3 +1592,100 @@ impl SCB {
 
 }
 
+#[cfg(all(armv6m, any(feature = "cpu_stats", feature = "trace")))]
+compile_error!("cpu_stats and trace need the DWT cycle counter, which ARMv6-M cores don't have");
+
+const DWT_ADR: usize = 0xE000_1000;
+const DEMCR_ADR: usize = 0xE000_EDFC;
+/// Data Watchpoint and Trace unit: only its cycle counter is used
+#[allow(clippy::upper_case_acronyms)]
+struct DWT {
+    ctrl: RW<DWT_ADR, 0x00>,
+    cyccnt: RW<DWT_ADR, 0x04>,
+    demcr: RW<DEMCR_ADR, 0x00>,
+}
+
+impl DWT {
+    const CTRL_CYCCNTENA: usize = 1;
+    const DEMCR_TRCENA: usize = 1 << 24;
+
+    const fn new() -> Self {
+        Self {
+            ctrl: RW::new(),
+            cyccnt: RW::new(),
+            demcr: RW::new(),
+        }
+    }
+
+    /// Trace must be enabled in the debug block before the DWT can count
+    fn start_cycles(&self) {
+        self.demcr.set(Self::DEMCR_TRCENA);
+        self.cyccnt.write(0);
+        self.ctrl.set(Self::CTRL_CYCCNTENA);
+    }
+}
+
+const MPU_ADR: usize = 0xE000_ED90;
+/// Named like NVIC and SCB, after the ARM manual
+#[allow(clippy::upper_case_acronyms)]
+struct MPU {
+    typer: RO<MPU_ADR, 0x00>,
+    ctrl: RW<MPU_ADR, 0x04>,
+    rnr: RW<MPU_ADR, 0x08>,
+    rbar: RW<MPU_ADR, 0x0C>,
+    rasr: RW<MPU_ADR, 0x10>,
+}
+
+impl MPU {
+    const CTRL_ENABLE: usize = 1;
+    const CTRL_PRIVDEFENA: usize = 1 << 2;
+    const TYPE_DREGION_POS: usize = 8;
+    const RBAR_VALID: usize = 1 << 4;
+    const RASR_ENABLE: usize = 1;
+    const RASR_SIZE_POS: usize = 1;
+    const RASR_AP_POS: usize = 24;
+    const RASR_XN: usize = 1 << 28;
+
+    const fn new() -> Self {
+        Self {
+            typer: RO::new(),
+            ctrl: RW::new(),
+            rnr: RW::new(),
+            rbar: RW::new(),
+            rasr: RW::new(),
+        }
+    }
+
+    /// Null-pointer guard in region 0, all other regions disabled till a Task is scheduled.
+    /// Default memory map stays as background for privileged code.
+    #[cfg(feature = "mpu_enabled")]
+    fn init(&self) {
+        self.ctrl.write(0);
+
+        let regions = (self.typer.read() >> Self::TYPE_DREGION_POS) & 0xFF;
+        for id in 1..regions {
+            self.rnr.write(id);
+            self.rasr.write(0);
+        }
+
+        if let Ok((rbar, rasr)) = MpuContext::region(0, 32, MpuAccess::NoAccess, MpuMemory::Normal) {
+            self.rbar.write(rbar | Self::RBAR_VALID);
+            self.rasr.write(rasr);
+        }
+
+        self.ctrl.write(Self::CTRL_PRIVDEFENA | Self::CTRL_ENABLE);
+        Self::sync();
+    }
+
+    /// New regions must be in place before next memory access
+    #[inline(always)]
+    fn sync() {
+        unsafe {
+            asm!(
+                "dsb",
+                "isb",
+            );
+        }
+    }
+}
+
@@ -28,3 +28,8 @@ pub(crate) mod mips;
 #[cfg(mips)]
 pub(crate) use mips as core;
 
+#[cfg(native)]
+pub(crate) mod native;
+#[cfg(native)]
+pub(crate) use native as core;
+
@@ -0,0 +1,465 @@
+//! RusTOS - Rust Real Time Operating System 
+//! Copyright (C) 2025 - Fabio Zanin - fabio.zanin93@outlook.com
+//! 
+//! This program is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License.
+//! 
+//! This program is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+//! 
+//! You should have received a copy of the GNU General Public License
+//! along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//! 
+//! ********************************************* HOST SIMULATION PORT ************************************************
+//! 
+//! This port runs RusTOS as an ordinary process on the machine that builds it (Linux, macOS...).
+//! It is selected when the compilation target is the host itself: build.rs emits `cfg(native)`.
+//! 
+//! The single core CPU is simulated with OS threads: every Task is executed by its own thread,
+//! but only the thread that owns the core is allowed to run, the others are parked on a condition variable.
+//! Interrupts masking is a lock owned by a thread, exception handlers are executed with interrupts masked.
+//! After the scheduler has been started, the thread that called Kernel::init becomes the SysTick peripheral.
+//! 
+//! Limitations: a context switch requested by SysTick (PendSV) is taken by the running Task the next time
+//! it enters the kernel (critical sections, SysCalls, idle sleep). A Task spinning without ever touching
+//! the kernel won't be preempted. As Kernel is a static, only one Kernel can be started per process.
+//! 
+//! *******************************************************************************************************************
+
+use core::cell::Cell;
+use core::fmt::{self, Display, Formatter};
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread::{self, ThreadId};
+use std::time;
+
+use crate::kernel::{SysCallArgs, SysCalls};
+use crate::kernel::Task;
+use crate::kernel::{Kernel, KERNEL};
+use crate::kernel::ExecContext;
+#[cfg(feature = "tickless")]
+use crate::kernel::Ticks;
+
+/// Period of the simulated SysTick
+const TICK_PERIOD: time::Duration = time::Duration::from_millis(1);
+
+/// Core owners numbering: zero is the SysTick thread
+static NEXT_THREAD: AtomicUsize = AtomicUsize::new(1);
+
+/// There is no hardware-stacked frame on the host: faults are never raised by the simulator,
+/// but the kernel fault handlers still need a frame to be compiled.
+#[repr(C)]
+pub struct ExceptionFrame {
+    pc: usize,
+    lr: usize,
+}
+
+impl ExceptionFrame {
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    pub fn lr(&self) -> usize {
+        self.lr
+    }
+
+    pub fn status(&self) -> usize {
+        0
+    }
+}
+
+/// No fault status on the host
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FaultRegisters {}
+
+impl FaultRegisters {
+    pub(crate) fn read() -> Self {
+        Self {}
+    }
+}
+
+impl Display for FaultRegisters {
+    fn fmt(&self, _f: &mut Formatter<'_>) -> fmt::Result {
+        Ok(())
+    }
+}
+
+/// Task context. Registers are kept by the thread executing the Task,
+/// so here we only keep track of which Task must be resumed.
+#[derive(Debug)]
+#[repr(C)]
+pub struct CpuContext {
+    task: usize,
+    /// Owner of the simulated core, new at every setup: threads of deleted Tasks stay parked
+    /// even if their TCB is reused
+    thread: usize,
+    sp: usize,
+    spawned: Cell<bool>,
+}
+
+impl CpuContext {
+    pub const fn new() -> Self {
+        Self {
+            task: 0,
+            thread: 0,
+            sp: 0,
+            spawned: Cell::new(false),
+        }
+    }
+
+    /// Hands the core to the Task that owns this context, creating its thread on first run
+    #[inline(always)]
+    pub(crate) unsafe fn load(&self) {
+        let mut core = CORE.lock();
+        core.running = self.thread;
+        if !self.spawned.replace(true) {
+            spawn_task(self.task, self.thread);
+        }
+        CORE.event.notify_all();
+    }
+
+    #[inline(always)]
+    pub(crate) unsafe fn load_psp(&self) {
+
+    }
+
+    pub(crate) fn sp(&self) -> usize {
+        self.sp
+    }
+}
+
+
+//*********************************************************************************************************************
+// SIMULATED CORE
+//*********************************************************************************************************************
+
+struct CoreState {
+    /// Thread that has masked interrupts: critical section or exception handler
+    masked_by: Option<ThreadId>,
+
+    /// Thread that owns the core
+    running: usize,
+
+    /// PendSV pending bit
+    pendsv: bool,
+
+    /// Count of served interrupts, used to wake up from WFI
+    irqs: usize,
+}
+
+struct Core {
+    state: Mutex<CoreState>,
+    event: Condvar,
+}
+
+static CORE: Core = Core::new();
+
+std::thread_local! {
+    /// Core owner number of this thread, see CpuContext. Zero for the SysTick thread.
+    static CURRENT: Cell<usize> = const { Cell::new(0) };
+
+    /// Thread is executing an exception handler
+    static HANDLER: Cell<bool> = const { Cell::new(false) };
+
+    /// SysCall arguments, like r4-r11 on Cortex-M
+    static ARGS: Cell<[usize; 8]> = const { Cell::new([0; 8]) };
+}
+
+impl Core {
+    const fn new() -> Self {
+        Self {
+            state: Mutex::new(CoreState {
+                masked_by: None,
+                running: 0,
+                pendsv: false,
+                irqs: 0,
+            }),
+            event: Condvar::new(),
+        }
+    }
+
+    /// A panicking Task must not take down the whole simulation, so poisoning is ignored
+    fn lock(&self) -> MutexGuard<'_, CoreState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn wait<'a>(&self, guard: MutexGuard<'a, CoreState>) -> MutexGuard<'a, CoreState> {
+        self.event.wait(guard).unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Like "cpsid i": waits if another thread has interrupts masked
+    fn mask(&self) {
+        let me = thread::current().id();
+        let mut core = self.lock();
+        while core.masked_by.is_some_and(|id| id != me) {
+            core = self.wait(core);
+        }
+        core.masked_by = Some(me);
+    }
+
+    /// Like "cpsie i": not nested, the first unmask re-enables interrupts
+    fn unmask(&self) {
+        let me = thread::current().id();
+        let mut core = self.lock();
+        if core.masked_by == Some(me) {
+            core.masked_by = None;
+            self.event.notify_all();
+        }
+    }
+
+    /// Parks the calling thread till its Task owns the core again
+    fn wait_core(&self, owner: usize) {
+        let mut core = self.lock();
+        while core.running != owner {
+            core = self.wait(core);
+        }
+    }
+
+    /// Like "wfi": returns when an interrupt has been served or a context switch is pending
+    fn wait_interrupt(&self) {
+        let mut core = self.lock();
+        let irqs = core.irqs;
+        while core.irqs == irqs && !core.pendsv {
+            core = self.wait(core);
+        }
+    }
+}
+
+#[inline]
+fn enter_handler() {
+    CORE.mask();
+    HANDLER.set(true);
+}
+
+#[inline]
+fn exit_handler() {
+    HANDLER.set(false);
+    CORE.unmask();
+}
+
+fn spawn_task(task: usize, owner: usize) {
+    let prio = unsafe { (*(task as *const Task)).prio };
+    let res = thread::Builder::new()
+        .name(std::format!("RusTOS-task-{}", prio))
+        .spawn(move || {
+            CURRENT.set(owner);
+            CORE.wait_core(owner);
+            let task = unsafe { &mut *(task as *mut Task) };
+            (task.task)(task)
+        });
+
+    if res.is_err() {
+        panic!("Unable to create a thread for task {}", prio);
+    }
+}
+
+/// PendSV emulation: when a context switch is pending, the running thread hands the core
+/// to the next Task and parks itself till it is scheduled again.
+fn take_pendsv() {
+    let me = CURRENT.get();
+    if me == 0 {
+        return;
+    }
+
+    CORE.mask();
+    let pending = core::mem::take(&mut CORE.lock().pendsv);
+    if pending {
+        HANDLER.set(true);
+        unsafe { KERNEL.access_unsafe().switch_to_next() };
+        HANDLER.set(false);
+    }
+    CORE.unmask();
+
+    CORE.wait_core(me);
+}
+
+
+//*********************************************************************************************************************
+// EXCEPTIONS
+//*********************************************************************************************************************
+
+#[allow(non_snake_case)]
+fn SysTick() {
+    enter_handler();
+    #[cfg(feature = "trace")]
+    crate::kernel::trace::isr_enter(15);
+
+    unsafe {
+        let k = KERNEL.access_unsafe();
+        k.inc_system_ticks();
+        k.schedule_next();
+    }
+
+    #[cfg(feature = "trace")]
+    crate::kernel::trace::isr_exit(15);
+    CORE.lock().irqs += 1;
+    CORE.event.notify_all();
+    exit_handler();
+}
+
+
+//***************************************************************************************************************
+// SYSCALLS IMPLEMENTATION
+//***************************************************************************************************************
+fn set_arg(n: usize, val: usize) {
+    let mut args = ARGS.get();
+    args[n] = val;
+    ARGS.set(args);
+}
+
+impl SysCallArgs for SysCalls {
+    fn set0(val: usize) { set_arg(0, val); }
+    fn set1(val: usize) { set_arg(1, val); }
+    fn set2(val: usize) { set_arg(2, val); }
+    fn set3(val: usize) { set_arg(3, val); }
+    fn set4(val: usize) { set_arg(4, val); }
+    fn set5(val: usize) { set_arg(5, val); }
+    fn set6(val: usize) { set_arg(6, val); }
+    fn set7(val: usize) { set_arg(7, val); }
+
+    fn arg0() -> usize { ARGS.get()[0] }
+    fn arg1() -> usize { ARGS.get()[1] }
+    fn arg2() -> usize { ARGS.get()[2] }
+    fn arg3() -> usize { ARGS.get()[3] }
+    fn arg4() -> usize { ARGS.get()[4] }
+    fn arg5() -> usize { ARGS.get()[5] }
+    fn arg6() -> usize { ARGS.get()[6] }
+    fn arg7() -> usize { ARGS.get()[7] }
+}
+
+impl SysCalls {
+    /// SVC emulation: handler is executed on the calling thread with interrupts masked,
+    /// then a pending context switch is taken like a tail-chained PendSV.
+    pub(crate) unsafe fn call(self) {
+        Self::set7(self as usize);
+        enter_handler();
+        KERNEL.access_unsafe().handle_syscall(SysCalls::arg7().into());
+        exit_handler();
+        take_pendsv();
+    }
+}
+
+
+//***************************************************************************************************************
+// KERNEL SIMULATION
+//***************************************************************************************************************
+impl Kernel {
+    #[inline(always)]
+    pub(crate) fn interrupt_disable() {
+        if !HANDLER.get() {
+            CORE.mask();
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn interrupt_enable() {
+        if !HANDLER.get() {
+            CORE.unmask();
+            take_pendsv();
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn nop() {
+        core::hint::spin_loop();
+    }
+
+    #[inline(always)]
+    pub(crate) fn core_sleep() {
+        CORE.wait_interrupt();
+        take_pendsv();
+    }
+
+    /// Starts the first Task, then the calling thread becomes the SysTick timer
+    pub(crate) fn start_task(task: &Task) -> ! {
+        unsafe { task.context.load() };
+        exit_handler();
+
+        loop {
+            thread::sleep(TICK_PERIOD);
+            SysTick();
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn request_context_switch(&self) {
+        CORE.lock().pendsv = true;
+        CORE.event.notify_all();
+    }
+
+    /// The simulated system can't be reset: the process ends
+    pub(crate) fn system_reset() -> ! {
+        std::process::exit(1);
+    }
+
+    #[inline(always)]
+    pub(crate) extern "C" fn get_context() -> ExecContext {
+        if HANDLER.get() || CURRENT.get() == 0 {
+            ExecContext::Privileged
+        } else {
+            ExecContext::Process
+        }
+    }
+}
+
+impl Task {
+    pub(crate) fn setup(&mut self) {
+        let pointer = &raw const *self;
+        let stack = unsafe { &*self.stack };
+        let top = stack.as_ptr_range().end as usize;
+
+        // Threads have their own stacks: watermark stays at zero
+        self.context.task = pointer as usize;
+        self.context.thread = NEXT_THREAD.fetch_add(1, Ordering::Relaxed);
+        self.context.sp = top;
+        self.stack_start = top;
+    }
+}
+
+
+//***************************************************************************************************************
+// CORE PERIPHERALS
+//***************************************************************************************************************
+
+/// Nothing to configure on the host: SysTick is started with the scheduler
+pub struct CorePeripherals;
+
+impl CorePeripherals {
+    pub const fn new() -> Self {
+        Self
+    }
+
+    pub fn setup(&mut self) {
+
+    }
+
+    /// Host SysTick is never suppressed: interrupts are unmasked only to wait for the next one,
+    /// as a masked thread would stop the simulated SysTick.
+    #[cfg(feature = "tickless")]
+    pub(crate) fn suppress_ticks(&mut self, _ticks: Ticks) -> Ticks {
+        CORE.unmask();
+        CORE.wait_interrupt();
+        CORE.mask();
+        0
+    }
+
+    /// Free-running time base of CPU statistics and trace: microseconds of the host clock
+    #[cfg(any(feature = "cpu_stats", feature = "trace"))]
+    pub(crate) fn cycles() -> u32 {
+        static START: std::sync::OnceLock<time::Instant> = std::sync::OnceLock::new();
+        START.get_or_init(time::Instant::now).elapsed().as_micros() as u32
+    }
+
+    #[cfg(any(feature = "cpu_stats", feature = "trace"))]
+    pub(crate) fn cycles_frequency() -> u32 {
+        1_000_000
+    }
+
+    #[inline]
+    pub fn sleep_on_exit(&self, _sleep: bool) {
+
+    }
+}
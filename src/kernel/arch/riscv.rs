@@ -11,4 +11,773 @@
 //! GNU General Public License for more details.
 //! 
 //! You should have received a copy of the GNU General Public License
-//! along with this program.  If not, see <https://www.gnu.org/licenses/>.
\ No newline at end of file
+//! along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//! 
+//! ********************************************* RISC-V PORT *********************************************************
+//! 
+//! Port for RV32 cores running in Machine mode only (RV32IMAC and friends), with a SiFive-like CLINT.
+//! 
+//! Every trap saves the whole register file on the stack of the interrupted Task (ExceptionFrame), then
+//! the handler runs on the kernel stack (ld_stack_start). A Task context is thus only its stack pointer.
+//! SysCalls are made with "ecall", SysTick is the CLINT machine timer and PendSV is emulated with the
+//! CLINT machine software interrupt: context switches happen only there, when no other trap is active.
+//! 
+//! Chip (hw) must provide CLINT_ADR, MTIME_FREQUENCY and the ExternalInterrupt handler (PLIC).
+//! 
+//! *******************************************************************************************************************
+
+use core::arch::{asm, global_asm, naked_asm};
+use core::fmt::{self, Display, Formatter};
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+use crate::hw::{CLINT_ADR, MTIME_FREQUENCY};
+use crate::kernel::{SysCallArgs, SysCalls};
+use crate::kernel::Task;
+use crate::kernel::{Kernel, KERNEL};
+use crate::kernel::CritSect;
+use crate::kernel::ExecContext;
+#[cfg(feature = "tickless")]
+use crate::kernel::Ticks;
+use crate::kernel::registers::*;
+
+/// Registers saved by trap entry on the interrupted stack.
+/// Every register is saved in the slot of its own number: x0 slot is used for MEPC, x2 (sp) for MSTATUS.
+/// gp and tp are never modified by Rust code, so their slots are not saved.
+#[repr(C)]
+pub struct ExceptionFrame {
+    mepc: usize,
+    ra: usize,
+    mstatus: usize,
+    gp: usize,
+    tp: usize,
+    t0: usize,
+    t1: usize,
+    t2: usize,
+    s0: usize,
+    s1: usize,
+    a0: usize,
+    a1: usize,
+    a2: usize,
+    a3: usize,
+    a4: usize,
+    a5: usize,
+    a6: usize,
+    a7: usize,
+    s2: usize,
+    s3: usize,
+    s4: usize,
+    s5: usize,
+    s6: usize,
+    s7: usize,
+    s8: usize,
+    s9: usize,
+    s10: usize,
+    s11: usize,
+    t3: usize,
+    t4: usize,
+    t5: usize,
+    t6: usize,
+}
+
+impl ExceptionFrame {
+    pub fn pc(&self) -> usize {
+        self.mepc
+    }
+
+    pub fn lr(&self) -> usize {
+        self.ra
+    }
+
+    pub fn status(&self) -> usize {
+        self.mstatus
+    }
+}
+
+/// Trap cause and value, as read when the fault was taken
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FaultRegisters {
+    pub mcause: usize,
+    /// Faulting address or instruction, depending on mcause
+    pub mtval: usize,
+}
+
+impl FaultRegisters {
+    pub(crate) fn read() -> Self {
+        let mcause: usize;
+        let mtval: usize;
+        unsafe {
+            asm!(
+                "csrr   {mcause}, mcause",
+                "csrr   {mtval}, mtval",
+                mcause = out(reg) mcause,
+                mtval = out(reg) mtval,
+            );
+        }
+        Self { mcause, mtval }
+    }
+}
+
+impl Display for FaultRegisters {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "mcause={:#x} mtval={:#010x}", self.mcause, self.mtval)
+    }
+}
+
+/// Registers are all inside the ExceptionFrame on Task stack: context is the stack pointer.
+/// SysCall arguments are kept here till "ecall" loads them into a0-a7.
+#[derive(Debug)]
+#[repr(C)]
+pub struct CpuContext {
+    sp: usize,
+    args: [usize; 8],
+}
+
+impl CpuContext {
+    pub const fn new() -> Self {
+        Self {
+            sp: 0,
+            args: [0; 8],
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) unsafe fn load(&self) {
+        // Registers are restored by trap exit, from the stack pointer returned by OSTrapHandler
+    }
+
+    pub(crate) fn sp(&self) -> usize {
+        self.sp
+    }
+}
+
+
+//*********************************************************************************************************************
+// TRAPS
+//*********************************************************************************************************************
+
+const MCAUSE_INTERRUPT: usize = 1 << 31;
+
+const MSTATUS_MIE: usize = 1 << 3;
+const MSTATUS_MPIE: usize = 1 << 7;
+const MSTATUS_MPP_MACHINE: usize = 0b11 << 11;
+
+const MIE_MSIE: usize = 1 << 3;
+const MIE_MTIE: usize = 1 << 7;
+const MIE_MEIE: usize = 1 << 11;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Interrupts {
+    MachineSoftware = 3,
+    MachineTimer = 7,
+    MachineExternal = 11,
+    Unknown,
+}
+
+impl From<usize> for Interrupts {
+    fn from(value: usize) -> Self {
+        match value {
+            3 => Interrupts::MachineSoftware,
+            7 => Interrupts::MachineTimer,
+            11 => Interrupts::MachineExternal,
+            _ => Interrupts::Unknown,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Exceptions {
+    InstructionMisaligned = 0,
+    InstructionFault = 1,
+    IllegalInstruction = 2,
+    Breakpoint = 3,
+    LoadMisaligned = 4,
+    LoadFault = 5,
+    StoreMisaligned = 6,
+    StoreFault = 7,
+    EnvCallUser = 8,
+    EnvCallMachine = 11,
+    Unknown,
+}
+
+impl From<usize> for Exceptions {
+    fn from(value: usize) -> Self {
+        match value {
+            0 => Exceptions::InstructionMisaligned,
+            1 => Exceptions::InstructionFault,
+            2 => Exceptions::IllegalInstruction,
+            3 => Exceptions::Breakpoint,
+            4 => Exceptions::LoadMisaligned,
+            5 => Exceptions::LoadFault,
+            6 => Exceptions::StoreMisaligned,
+            7 => Exceptions::StoreFault,
+            8 => Exceptions::EnvCallUser,
+            11 => Exceptions::EnvCallMachine,
+            _ => Exceptions::Unknown,
+        }
+    }
+}
+
+/// Number of traps being served: zero when a Task is running.
+/// Interrupts are never nested, only a fault handler can raise a SysCall inside a trap.
+#[no_mangle]
+static TRAP_NESTING: AtomicUsize = AtomicUsize::new(0);
+
+/// Frame of the SysCall being served, where arguments are read from
+static TRAP_FRAME: AtomicPtr<ExceptionFrame> = AtomicPtr::new(ptr::null_mut());
+
+/// SysCall arguments prepared inside a trap handler, not to overwrite the ones of the running Task
+static mut ISR_ARGS: [usize; 8] = [0; 8];
+
+// Trap vector (mtvec direct mode): it must be 4 bytes aligned, so it is written as global assembly.
+global_asm!(
+    ".section .text.OSTrapEntry, \"ax\"",
+    ".balign 4",
+    ".global OSTrapEntry",
+    "OSTrapEntry:",
+    "    addi    sp, sp, -128",
+    "    sw      x1, 4(sp)",
+    "    sw      x5, 20(sp)",
+    "    sw      x6, 24(sp)",
+    "    sw      x7, 28(sp)",
+    "    sw      x8, 32(sp)",
+    "    sw      x9, 36(sp)",
+    "    sw      x10, 40(sp)",
+    "    sw      x11, 44(sp)",
+    "    sw      x12, 48(sp)",
+    "    sw      x13, 52(sp)",
+    "    sw      x14, 56(sp)",
+    "    sw      x15, 60(sp)",
+    "    sw      x16, 64(sp)",
+    "    sw      x17, 68(sp)",
+    "    sw      x18, 72(sp)",
+    "    sw      x19, 76(sp)",
+    "    sw      x20, 80(sp)",
+    "    sw      x21, 84(sp)",
+    "    sw      x22, 88(sp)",
+    "    sw      x23, 92(sp)",
+    "    sw      x24, 96(sp)",
+    "    sw      x25, 100(sp)",
+    "    sw      x26, 104(sp)",
+    "    sw      x27, 108(sp)",
+    "    sw      x28, 112(sp)",
+    "    sw      x29, 116(sp)",
+    "    sw      x30, 120(sp)",
+    "    sw      x31, 124(sp)",
+    "    csrr    t0, mepc",
+    "    sw      t0, 0(sp)",
+    "    csrr    t0, mstatus",
+    "    sw      t0, 8(sp)",
+    "    mv      a0, sp",
+    // Moves to kernel stack, unless we are already on it
+    "    la      t0, TRAP_NESTING",
+    "    lw      t1, 0(t0)",
+    "    addi    t2, t1, 1",
+    "    sw      t2, 0(t0)",
+    "    bnez    t1, 1f",
+    "    la      sp, ld_stack_start",
+    "1:",
+    "    call    OSTrapHandler",
+    "    la      t0, TRAP_NESTING",
+    "    lw      t1, 0(t0)",
+    "    addi    t1, t1, -1",
+    "    sw      t1, 0(t0)",
+    // Returned frame could be the one of another Task
+    "    mv      sp, a0",
+    "    j       OSTrapRestore",
+    "",
+    ".balign 4",
+    ".global OSTrapRestore",
+    "OSTrapRestore:",
+    "    lw      t0, 0(sp)",
+    "    csrw    mepc, t0",
+    "    lw      t0, 8(sp)",
+    "    csrw    mstatus, t0",
+    "    lw      x1, 4(sp)",
+    "    lw      x5, 20(sp)",
+    "    lw      x6, 24(sp)",
+    "    lw      x7, 28(sp)",
+    "    lw      x8, 32(sp)",
+    "    lw      x9, 36(sp)",
+    "    lw      x10, 40(sp)",
+    "    lw      x11, 44(sp)",
+    "    lw      x12, 48(sp)",
+    "    lw      x13, 52(sp)",
+    "    lw      x14, 56(sp)",
+    "    lw      x15, 60(sp)",
+    "    lw      x16, 64(sp)",
+    "    lw      x17, 68(sp)",
+    "    lw      x18, 72(sp)",
+    "    lw      x19, 76(sp)",
+    "    lw      x20, 80(sp)",
+    "    lw      x21, 84(sp)",
+    "    lw      x22, 88(sp)",
+    "    lw      x23, 92(sp)",
+    "    lw      x24, 96(sp)",
+    "    lw      x25, 100(sp)",
+    "    lw      x26, 104(sp)",
+    "    lw      x27, 108(sp)",
+    "    lw      x28, 112(sp)",
+    "    lw      x29, 116(sp)",
+    "    lw      x30, 120(sp)",
+    "    lw      x31, 124(sp)",
+    "    addi    sp, sp, 128",
+    "    mret",
+);
+
+extern "C" {
+    /// External interrupts are routed by the chip interrupt controller (PLIC)
+    fn ExternalInterrupt();
+}
+
+#[unsafe(naked)]
+#[no_mangle]
+#[link_section = ".init"]
+#[allow(non_snake_case)]
+unsafe extern "C" fn __ENTRY() {
+    naked_asm!(
+        /* Only hart 0 executes RusTOS */
+        "csrr   t0, mhartid",
+        "bnez   t0, 4f",
+        "csrw   mie, zero",
+        "csrci  mstatus, 8",
+        "la     sp, ld_stack_start",
+        "la     t0, OSTrapEntry",
+        "csrw   mtvec, t0",
+        /* Copy the data segment initializers from flash to SRAM */
+        "la     a0, ld_data_start",
+        "la     a1, ld_data_end",
+        "la     a2, ld_data",
+        "j      1f",
+        /* Loads the data segment */
+        "0:",
+        "lw     t0, 0(a2)",
+        "sw     t0, 0(a0)",
+        "addi   a0, a0, 4",
+        "addi   a2, a2, 4",
+        "1:",
+        "bltu   a0, a1, 0b",
+        /* Zero fill the bss segment. */
+        "la     a0, ld_bss_start",
+        "la     a1, ld_bss_end",
+        "j      3f",
+        "2:",
+        "sw     zero, 0(a0)",
+        "addi   a0, a0, 4",
+        "3:",
+        "bltu   a0, a1, 2b",
+        /* Latch the crash log of the previous run */
+        "call   OSBoot",
+        /* Call the application's entry point.*/
+        "j      OSEntry",
+        /* Other harts sleep forever */
+        "4:",
+        "wfi",
+        "j      4b",
+    );
+}
+
+/// Called by trap entry with the frame saved on the interrupted stack.
+/// Returns the frame to be restored: it is a different one only after a context switch.
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn OSTrapHandler(frame: &mut ExceptionFrame) -> usize {
+    let mcause: usize;
+    unsafe {
+        asm!(
+            "csrr   {out}, mcause",
+            out = out(reg) mcause,
+        );
+    }
+
+    let sp = frame as *mut ExceptionFrame as usize;
+
+    if mcause & MCAUSE_INTERRUPT != 0 {
+        match (mcause & !MCAUSE_INTERRUPT).into() {
+            Interrupts::MachineSoftware => return SoftwareInterrupt(sp),
+            Interrupts::MachineTimer => SysTick(),
+            Interrupts::MachineExternal => unsafe { ExternalInterrupt() },
+            Interrupts::Unknown => {},
+        }
+        return sp;
+    }
+
+    let running = unsafe { KERNEL.access_unsafe().running_mut() };
+    // A trap inside a trap can only be a fault of an handler or of the Kernel
+    let handler = TRAP_NESTING.load(Ordering::Relaxed) > 1;
+    match mcause.into() {
+        Exceptions::EnvCallMachine | Exceptions::EnvCallUser => {
+            // Returns after "ecall" instruction
+            frame.mepc += 4;
+            let served = TRAP_FRAME.swap(frame, Ordering::Relaxed);
+            EnvCall();
+            TRAP_FRAME.store(served, Ordering::Relaxed);
+        },
+
+        Exceptions::InstructionFault |
+        Exceptions::LoadFault |
+        Exceptions::StoreFault => crate::kernel::OSBusFault(frame, running, handler),
+
+        Exceptions::InstructionMisaligned |
+        Exceptions::IllegalInstruction |
+        Exceptions::LoadMisaligned |
+        Exceptions::StoreMisaligned => crate::kernel::OSUsageFault(frame, running, handler),
+
+        Exceptions::Breakpoint |
+        Exceptions::Unknown => crate::kernel::OSHardFault(frame, running, handler),
+    }
+
+    sp
+}
+
+/// Inside a trap handler: interrupts are disabled
+#[allow(non_snake_case)]
+fn EnvCall() {
+    let syscall: SysCalls = SysCalls::arg7().into();
+
+    // Access Kernel without critical section, as interrupts are disabled
+    unsafe {
+        let k = KERNEL.access_unsafe();
+        k.handle_syscall(syscall);
+    };
+}
+
+/// PendSV-like context switch: served only when no other trap is active,
+/// so the frame is always on the stack of the running Task.
+#[allow(non_snake_case)]
+fn SoftwareInterrupt(sp: usize) -> usize {
+    unsafe {
+        let k = KERNEL.access_unsafe();
+        k.core.clint.clear_software();
+        k.running_mut().context.sp = sp;
+        k.switch_to_next();
+        k.running().context.sp
+    }
+}
+
+#[allow(non_snake_case)]
+fn SysTick() {
+    let cs = CritSect::activate();
+    #[cfg(feature = "trace")]
+    crate::kernel::trace::isr_enter(Interrupts::MachineTimer as usize);
+
+    KERNEL.access(&cs).core.clint.next_tick();
+    KERNEL.access(&cs).inc_system_ticks();
+    KERNEL.access(&cs).schedule_next();
+
+    #[cfg(feature = "trace")]
+    crate::kernel::trace::isr_exit(Interrupts::MachineTimer as usize);
+    cs.deactivate();
+}
+
+//***************************************************************************************************************
+// SYSCALLS IMPLEMENTATION
+//***************************************************************************************************************
+
+/// Arguments of next SysCall: the running Task ones, or the trap handler ones
+fn syscall_args() -> &'static mut [usize; 8] {
+    unsafe {
+        if TRAP_NESTING.load(Ordering::Relaxed) == 0 {
+            &mut KERNEL.access_unsafe().running_mut().context.args
+        } else {
+            let args = &raw mut ISR_ARGS;
+            &mut *args
+        }
+    }
+}
+
+/// Arguments of the SysCall being served, as loaded in a0-a7 by "ecall"
+fn served_frame() -> &'static ExceptionFrame {
+    unsafe { &*TRAP_FRAME.load(Ordering::Relaxed) }
+}
+
+impl SysCallArgs for SysCalls {
+    fn set0(val: usize) { syscall_args()[0] = val; }
+    fn set1(val: usize) { syscall_args()[1] = val; }
+    fn set2(val: usize) { syscall_args()[2] = val; }
+    fn set3(val: usize) { syscall_args()[3] = val; }
+    fn set4(val: usize) { syscall_args()[4] = val; }
+    fn set5(val: usize) { syscall_args()[5] = val; }
+    fn set6(val: usize) { syscall_args()[6] = val; }
+    fn set7(val: usize) { syscall_args()[7] = val; }
+
+    fn arg0() -> usize { served_frame().a0 }
+    fn arg1() -> usize { served_frame().a1 }
+    fn arg2() -> usize { served_frame().a2 }
+    fn arg3() -> usize { served_frame().a3 }
+    fn arg4() -> usize { served_frame().a4 }
+    fn arg5() -> usize { served_frame().a5 }
+    fn arg6() -> usize { served_frame().a6 }
+    fn arg7() -> usize { served_frame().a7 }
+}
+
+impl SysCalls {
+    pub(crate) unsafe fn call(self) {
+        Self::set7(self as usize);
+        let args = *syscall_args();
+        asm!(
+            "ecall",
+            in("a0") args[0],
+            in("a1") args[1],
+            in("a2") args[2],
+            in("a3") args[3],
+            in("a4") args[4],
+            in("a5") args[5],
+            in("a6") args[6],
+            in("a7") args[7],
+        );
+    }
+}
+
+
+//***************************************************************************************************************
+// KERNEL ASSEMBLY
+//***************************************************************************************************************
+impl Kernel {
+    #[inline(always)]
+    pub(crate) fn interrupt_disable() {
+        unsafe {
+            asm!("csrci  mstatus, {mie}", mie = const MSTATUS_MIE);
+        }
+    }
+
+    /// Traps are not nested: interrupts stay disabled till trap exit
+    #[inline(always)]
+    pub(crate) fn interrupt_enable() {
+        if TRAP_NESTING.load(Ordering::Relaxed) == 0 {
+            unsafe {
+                asm!("csrsi  mstatus, {mie}", mie = const MSTATUS_MIE);
+            }
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn nop() {
+        unsafe {
+            asm!("nop");
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn core_sleep() {
+        unsafe {
+            asm!("wfi");
+        }
+    }
+
+    /// Core interrupts are enabled only here, so that boot stack can become the kernel stack
+    #[inline(always)]
+    pub(crate) fn start_task(task: &Task) -> ! {
+        unsafe {
+            TRAP_NESTING.store(0, Ordering::Relaxed);
+            asm!(
+                "csrs   mie, {mie}",
+                "mv     sp, {sp}",
+                "j      OSTrapRestore",
+                mie = in(reg) MIE_MSIE | MIE_MTIE | MIE_MEIE,
+                sp = in(reg) task.context.sp,
+                options(noreturn)
+            );
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn request_context_switch(&self) {
+        self.core.clint.set_software();
+    }
+
+    /// There is no standard reset on RISC-V: boot code is executed again with interrupts disabled,
+    /// and it copies .data again from its load image, that the linker script keeps apart in ROM
+    pub(crate) fn system_reset() -> ! {
+        unsafe {
+            asm!(
+                "csrci  mstatus, {mie}",
+                "la     t0, __ENTRY",
+                "jr     t0",
+                mie = const MSTATUS_MIE,
+                options(noreturn)
+            );
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) extern "C" fn get_context() -> ExecContext {
+        if TRAP_NESTING.load(Ordering::Relaxed) == 0 {
+            ExecContext::Process
+        } else {
+            ExecContext::Privileged
+        }
+    }
+}
+
+impl Task {
+    pub(crate) fn setup(&mut self) {
+        let pointer = &raw const *self;
+        let stack = unsafe { &mut *(self.stack as *mut [usize]) };
+        let len = stack.len();
+
+        // Calling convention wants a 16 bytes aligned stack pointer
+        let range = stack.as_mut_ptr_range();
+        let top = range.end as usize & !0xF;
+        let frame = unsafe { &mut *((top - size_of::<ExceptionFrame>()) as *mut ExceptionFrame) };
+
+        frame.mepc = self.task as usize;
+        frame.ra = 0;
+        // Machine mode, interrupts enabled by "mret"
+        frame.mstatus = MSTATUS_MPP_MACHINE | MSTATUS_MPIE;
+        frame.a0 = pointer as usize;
+
+        self.context.sp = frame as *mut ExceptionFrame as usize;
+        self.stack_start = (&stack[len - 1] as *const usize) as usize;
+    }
+}
+
+//***************************************************************************************************************
+// CORE PERIPHERALS
+//***************************************************************************************************************
+
+pub struct CorePeripherals {
+    clint: Clint,
+}
+
+impl CorePeripherals {
+    pub const fn new() -> Self {
+        Self {
+            clint: Clint::new(),
+        }
+    }
+
+    pub fn setup(&mut self) {
+        unsafe {
+            asm!(
+                "la     {tmp}, OSTrapEntry",
+                "csrw   mtvec, {tmp}",
+                tmp = out(reg) _,
+            );
+        }
+
+        self.clint.clear_software();
+        self.clint.init();
+    }
+
+    #[cfg(feature = "tickless")]
+    #[inline]
+    pub(crate) fn suppress_ticks(&mut self, ticks: Ticks) -> Ticks {
+        self.clint.suppress_ticks(ticks)
+    }
+
+    /// Free-running time base of CPU statistics and trace: low word of CLINT mtime
+    #[cfg(any(feature = "cpu_stats", feature = "trace"))]
+    #[inline]
+    pub(crate) fn cycles() -> u32 {
+        Clint::new().mtime_lo.read() as u32
+    }
+
+    #[cfg(any(feature = "cpu_stats", feature = "trace"))]
+    pub(crate) fn cycles_frequency() -> u32 {
+        let mtime: crate::kernel::Hz = MTIME_FREQUENCY.into();
+        let mtime: usize = mtime.into();
+        mtime as u32
+    }
+
+    /// Not available on RISC-V: idle Task sleeps with "wfi"
+    #[inline]
+    pub fn sleep_on_exit(&self, _sleep: bool) {
+
+    }
+}
+
+
+/// Core Local Interruptor, hart 0 registers
+struct Clint {
+    msip: RW<CLINT_ADR, 0x0000>,
+    mtimecmp_lo: RW<CLINT_ADR, 0x4000>,
+    mtimecmp_hi: RW<CLINT_ADR, 0x4004>,
+    mtime_lo: RO<CLINT_ADR, 0xBFF8>,
+    mtime_hi: RO<CLINT_ADR, 0xBFFC>,
+
+    /// mtime counts for a tick
+    reload: u64,
+    /// mtime of next tick
+    compare: u64,
+}
+
+impl Clint {
+    const fn new() -> Self {
+        Self {
+            msip: RW::new(),
+            mtimecmp_lo: RW::new(),
+            mtimecmp_hi: RW::new(),
+            mtime_lo: RO::new(),
+            mtime_hi: RO::new(),
+            reload: 0,
+            compare: 0,
+        }
+    }
+
+    fn init(&mut self) {
+        let mtime: crate::kernel::Hz = MTIME_FREQUENCY.into();
+        let mtime: usize = mtime.into();
+        self.reload = (mtime / 1000) as u64;
+
+        self.compare = self.now() + self.reload;
+        self.set_compare(self.compare);
+    }
+
+    /// Sleeps with compare moved forward by the given ticks, unless another interrupt comes first.
+    /// Returns the elapsed ticks that SysTick won't count.
+    #[cfg(feature = "tickless")]
+    fn suppress_ticks(&mut self, ticks: Ticks) -> Ticks {
+        let next = self.compare;
+        self.set_compare(next + (ticks as u64 - 1) * self.reload);
+
+        Kernel::core_sleep();
+
+        let now = self.now();
+        let passed = if now < next {
+            0
+        } else {
+            ((now - next) / self.reload + 1).min(ticks as u64)
+        };
+
+        // Compare is set back to last elapsed tick: SysTick is pending and it will count it
+        let skipped = passed.saturating_sub(1);
+        self.compare = next + skipped * self.reload;
+        self.set_compare(self.compare);
+
+        skipped as Ticks
+    }
+
+    /// Next compare is computed from the previous one, not to accumulate latency
+    fn next_tick(&mut self) {
+        self.compare += self.reload;
+        self.set_compare(self.compare);
+    }
+
+    fn now(&self) -> u64 {
+        loop {
+            let hi = self.mtime_hi.read();
+            let lo = self.mtime_lo.read();
+            if hi == self.mtime_hi.read() {
+                return ((hi as u64) << 32) | lo as u64;
+            }
+        }
+    }
+
+    /// Writing sequence avoids a spurious match between the two halves
+    fn set_compare(&self, val: u64) {
+        self.mtimecmp_hi.write(usize::MAX);
+        self.mtimecmp_lo.write(val as usize);
+        self.mtimecmp_hi.write((val >> 32) as usize);
+    }
+
+    #[inline]
+    fn set_software(&self) {
+        self.msip.write(1);
+    }
+
+    #[inline]
+    fn clear_software(&self) {
+        self.msip.write(0);
+    }
+}
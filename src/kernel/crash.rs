@@ -0,0 +1,184 @@
+//! RusTOS - Rust Real Time Operating System
+//! Copyright (C) 2025 - Fabio Zanin - fabio.zanin93@outlook.com
+//!
+//! This program is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License.
+//!
+//! This program is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+//!
+//! You should have received a copy of the GNU General Public License
+//! along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//!
+//! ************************************************ CRASH LOG OF RusTOS **********************************************
+//!
+//! Why the system has been reset, kept in .noinit RAM so that it survives a soft reset.
+//! Panics, faults that reset the system and resets requested by the application write the reason, the priority
+//! of the running Task, the panic message and the last fault report just before resetting.
+//! At boot (before OSEntry) the record is validated, copied to .bss and invalidated, so that a later
+//! power-on, reset pin or watchdog reset is not mistaken for a crash: OSEntry and Tasks read it with last_crash().
+//!
+//! *******************************************************************************************************************
+
+use core::cell::UnsafeCell;
+use core::fmt::{self, Write};
+use core::mem::MaybeUninit;
+use core::str;
+
+use crate::kernel::{FaultReport, Kernel};
+
+/// Bytes of the panic message kept, longer messages are truncated
+pub const CRASH_MESSAGE_SIZE: usize = 160;
+
+/// Marks a record written by the Kernel: random RAM at power-on won't match it
+const CRASH_MAGIC: usize = 0xC4A5_10C5;
+
+/// What caused the last reset
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ResetReason {
+    /// No crash log: power-on, reset pin or watchdog
+    External,
+    /// Kernel or a Task panicked, see CrashLog::message()
+    Panic,
+    /// Fault hook asked for a reset, or the idle Task faulted
+    Fault,
+    /// Application called crash::reset()
+    Requested,
+}
+
+/// Crash record written before a soft reset
+#[derive(Clone, Copy, Debug)]
+pub struct CrashLog {
+    pub reason: ResetReason,
+    /// Priority of the Task running at the time of the crash, IDLE_PRIO for idle or before the Kernel started
+    pub prio: usize,
+    /// Last fault raised before the reset, even if it did not cause it
+    pub fault: Option<FaultReport>,
+    len: usize,
+    message: [u8; CRASH_MESSAGE_SIZE],
+}
+
+impl CrashLog {
+    const fn new() -> Self {
+        Self {
+            reason: ResetReason::External,
+            prio: 0,
+            fault: None,
+            len: 0,
+            message: [0; CRASH_MESSAGE_SIZE],
+        }
+    }
+
+    /// Panic message with its location, empty if the reset was not caused by a panic
+    pub fn message(&self) -> &str {
+        let len = self.len.min(CRASH_MESSAGE_SIZE);
+        // Truncation may have split a character
+        match str::from_utf8(&self.message[..len]) {
+            Ok(message) => message,
+            Err(err) => unsafe { str::from_utf8_unchecked(&self.message[..err.valid_up_to()]) },
+        }
+    }
+}
+
+impl Write for CrashLog {
+    /// Appends to the message till it is full
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let len = s.len().min(CRASH_MESSAGE_SIZE - self.len);
+        self.message[self.len..self.len + len].copy_from_slice(&s.as_bytes()[..len]);
+        self.len += len;
+        Ok(())
+    }
+}
+
+//*********************************************************************************************************************
+// NO-INIT RECORD
+//*********************************************************************************************************************
+
+/// Magic is written last, so a record half-written when power went down is discarded
+#[repr(C)]
+struct Record {
+    magic: usize,
+    log: CrashLog,
+}
+
+/// Written only with interrupts disabled: by fault handlers, the panic handler and boot code
+struct NoInit<T>(UnsafeCell<MaybeUninit<T>>);
+
+unsafe impl<T> Sync for NoInit<T> {}
+
+#[cfg_attr(not(native), link_section = ".noinit")]
+static RECORD: NoInit<Record> = NoInit(UnsafeCell::new(MaybeUninit::uninit()));
+
+/// Crash log found at boot
+static mut LAST_CRASH: Option<CrashLog> = None;
+
+fn record() -> *mut Record {
+    RECORD.0.get() as *mut Record
+}
+
+/// Latches the record left by the previous run, then starts a new empty one.
+/// Called by boot code before OSEntry, when nothing else is running.
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn OSBoot() {
+    unsafe {
+        let record = record();
+        // Log is read only if the Kernel has written it: random RAM is not a valid enum or Option
+        let magic = (&raw const (*record).magic).read_volatile();
+        if magic == CRASH_MAGIC {
+            LAST_CRASH = Some((&raw const (*record).log).read());
+        }
+
+        record.write(Record {
+            magic: 0,
+            log: CrashLog::new(),
+        });
+    }
+}
+
+/// Keeps the last fault report, in case a reset follows
+pub(crate) fn save_fault(report: &FaultReport) {
+    unsafe { (*record()).log.fault = Some(*report) };
+}
+
+/// Seals the record with the reason of the reset that is going to happen
+pub(crate) fn save_reset(reason: ResetReason, prio: usize) {
+    unsafe {
+        let record = record();
+        (*record).log.reason = reason;
+        (*record).log.prio = prio;
+        (&raw mut (*record).magic).write_volatile(CRASH_MAGIC);
+    }
+}
+
+/// Writes the panic message into the record
+pub(crate) fn save_message(args: fmt::Arguments) {
+    unsafe {
+        let log = &mut (*record()).log;
+        log.len = 0;
+        let _ = log.write_fmt(args);
+    }
+}
+
+/// Reason of the last reset
+pub fn reset_reason() -> ResetReason {
+    match last_crash() {
+        Some(log) => log.reason,
+        None => ResetReason::External,
+    }
+}
+
+/// Crash log left by the previous run, if it ended with a soft reset made by the Kernel
+pub fn last_crash() -> Option<CrashLog> {
+    unsafe { (&raw const LAST_CRASH).read() }
+}
+
+/// Resets the system, recording it as requested by the application
+pub fn reset() -> ! {
+    Kernel::interrupt_disable();
+    save_reset(ResetReason::Requested, Kernel::running_prio());
+    Kernel::system_reset();
+}
@@ -0,0 +1,459 @@
+//! RusTOS - Rust Real Time Operating System
+//! Copyright (C) 2025 - Fabio Zanin - fabio.zanin93@outlook.com
+//!
+//! This program is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License.
+//!
+//! This program is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+//!
+//! You should have received a copy of the GNU General Public License
+//! along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//!
+//! ************************************************** HEAP OF RusTOS *************************************************
+//!
+//! Two-Level Segregated Fit allocator: free blocks are kept in lists indexed by a first level (power of two)
+//! and a second level (linear subdivision of that power), both tracked by bitmaps.
+//! Allocation and deallocation are O(1): a couple of bit scans and a bounded number of list operations,
+//! so the heap can be used by Tasks without breaking their timing.
+//!
+//! With "heap" feature, HEAP is the global allocator and takes all the RAM left after .bss, .uninit and .noinit.
+//!
+//! *******************************************************************************************************************
+
+use core::alloc::{GlobalAlloc, Layout};
+use core::cell::{Cell, UnsafeCell};
+use core::mem::size_of;
+use core::ptr::null_mut;
+
+use crate::kernel::CritSect;
+
+//*********************************************************************************************************************
+// TLSF PARAMETERS
+//*********************************************************************************************************************
+
+/// Every block size and payload is aligned to a word
+const ALIGN: usize = size_of::<usize>();
+const ALIGN_LOG: usize = ALIGN.trailing_zeros() as usize;
+
+/// Each power of two is split into 2^SL_LOG lists
+const SL_LOG: usize = 3;
+const SL_COUNT: usize = 1 << SL_LOG;
+
+/// Blocks smaller than SMALL_BLOCK all belong to first level 0, linearly spaced by ALIGN
+const FL_SHIFT: usize = SL_LOG + ALIGN_LOG;
+const SMALL_BLOCK: usize = 1 << FL_SHIFT;
+const FL_COUNT: usize = 24;
+
+/// Blocks bigger than this cannot be indexed, so the heap region is cut down to it
+const MAX_BLOCK: usize = (1 << (FL_COUNT + FL_SHIFT - 1)) - ALIGN;
+
+/// Header is always present: previous physical block and size, with FREE flag in bit 0
+const HEADER: usize = 2 * size_of::<usize>();
+/// A free block must also hold its two free list links
+const MIN_BLOCK: usize = 4 * size_of::<usize>();
+
+const FREE: usize = 1;
+
+//*********************************************************************************************************************
+// BLOCKS
+//*********************************************************************************************************************
+
+#[repr(C)]
+struct Block {
+    prev_phys: *mut Block,
+    size: usize,
+    /// Valid only when the block is free: they overlap the payload
+    next_free: *mut Block,
+    prev_free: *mut Block,
+}
+
+impl Block {
+    #[inline(always)]
+    unsafe fn size(b: *mut Block) -> usize {
+        (*b).size & !FREE
+    }
+
+    #[inline(always)]
+    unsafe fn is_free(b: *mut Block) -> bool {
+        (*b).size & FREE != 0
+    }
+
+    #[inline(always)]
+    unsafe fn next_phys(b: *mut Block) -> *mut Block {
+        (b as usize + Block::size(b)) as *mut Block
+    }
+
+    #[inline(always)]
+    unsafe fn payload(b: *mut Block) -> *mut u8 {
+        (b as usize + HEADER) as *mut u8
+    }
+
+    #[inline(always)]
+    unsafe fn from_payload(ptr: *mut u8) -> *mut Block {
+        (ptr as usize - HEADER) as *mut Block
+    }
+
+    /// Cuts `b` at `size` bytes and returns the remaining block, that is marked free
+    unsafe fn split(b: *mut Block, size: usize) -> *mut Block {
+        let rest = (b as usize + size) as *mut Block;
+        (*rest).size = (Block::size(b) - size) | FREE;
+        (*rest).prev_phys = b;
+        (*b).size = size | ((*b).size & FREE);
+        (*Block::next_phys(rest)).prev_phys = rest;
+        rest
+    }
+}
+
+#[inline(always)]
+const fn align_up(value: usize, align: usize) -> usize {
+    (value + align - 1) & !(align - 1)
+}
+
+/// First and second level lists of a block of given size
+#[inline(always)]
+fn mapping(size: usize) -> (usize, usize) {
+    if size < SMALL_BLOCK {
+        (0, size >> ALIGN_LOG)
+    } else {
+        let msb = (usize::BITS - 1 - size.leading_zeros()) as usize;
+        (msb - FL_SHIFT + 1, (size >> (msb - SL_LOG)) & (SL_COUNT - 1))
+    }
+}
+
+/// Size rounded up to the next list, so that every block found there fits
+#[inline(always)]
+fn round_size(size: usize) -> usize {
+    if size < SMALL_BLOCK {
+        size
+    } else {
+        let msb = (usize::BITS - 1 - size.leading_zeros()) as usize;
+        size + (1 << (msb - SL_LOG)) - 1
+    }
+}
+
+//*********************************************************************************************************************
+// TLSF CONTROL STRUCTURE
+//*********************************************************************************************************************
+
+struct Tlsf {
+    fl_bitmap: u32,
+    sl_bitmap: [u8; FL_COUNT],
+    heads: [[*mut Block; SL_COUNT]; FL_COUNT],
+}
+
+impl Tlsf {
+    const fn new() -> Self {
+        Tlsf {
+            fl_bitmap: 0,
+            sl_bitmap: [0; FL_COUNT],
+            heads: [[null_mut(); SL_COUNT]; FL_COUNT],
+        }
+    }
+
+    unsafe fn insert(&mut self, b: *mut Block) {
+        let (fl, sl) = mapping(Block::size(b));
+        let head = self.heads[fl][sl];
+
+        (*b).size |= FREE;
+        (*b).prev_free = null_mut();
+        (*b).next_free = head;
+        if !head.is_null() {
+            (*head).prev_free = b;
+        }
+
+        self.heads[fl][sl] = b;
+        self.fl_bitmap |= 1 << fl;
+        self.sl_bitmap[fl] |= 1 << sl;
+    }
+
+    unsafe fn remove(&mut self, b: *mut Block) {
+        let (fl, sl) = mapping(Block::size(b));
+        let next = (*b).next_free;
+        let prev = (*b).prev_free;
+
+        if !next.is_null() {
+            (*next).prev_free = prev;
+        }
+        if !prev.is_null() {
+            (*prev).next_free = next;
+        } else {
+            self.heads[fl][sl] = next;
+            if next.is_null() {
+                self.sl_bitmap[fl] &= !(1 << sl);
+                if self.sl_bitmap[fl] == 0 {
+                    self.fl_bitmap &= !(1 << fl);
+                }
+            }
+        }
+
+        (*b).size &= !FREE;
+    }
+
+    /// Head of the first non-empty list whose blocks are at least `size` bytes
+    fn find(&self, size: usize) -> Option<*mut Block> {
+        let (fl, sl) = mapping(round_size(size));
+        if fl >= FL_COUNT {
+            return None;
+        }
+
+        let sl_map = self.sl_bitmap[fl] as u32 & (!0u32 << sl);
+        let (fl, sl_map) = if sl_map != 0 {
+            (fl, sl_map)
+        } else {
+            let fl_map = self.fl_bitmap & (!0u32).checked_shl(fl as u32 + 1).unwrap_or(0);
+            if fl_map == 0 {
+                return None;
+            }
+            let fl = fl_map.trailing_zeros() as usize;
+            (fl, self.sl_bitmap[fl] as u32)
+        };
+
+        Some(self.heads[fl][sl_map.trailing_zeros() as usize])
+    }
+
+    /// Joins a free block with its free physical neighbours, then puts it in its list
+    unsafe fn release(&mut self, mut b: *mut Block) {
+        let next = Block::next_phys(b);
+        if Block::is_free(next) {
+            self.remove(next);
+            (*b).size += Block::size(next);
+        }
+
+        let prev = (*b).prev_phys;
+        if !prev.is_null() && Block::is_free(prev) {
+            self.remove(prev);
+            (*prev).size += Block::size(b);
+            b = prev;
+        }
+
+        (*Block::next_phys(b)).prev_phys = b;
+        self.insert(b);
+    }
+}
+
+//*********************************************************************************************************************
+// HEAP
+//*********************************************************************************************************************
+
+/// Usage statistics of a Heap, in bytes (block headers included)
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HeapStats {
+    pub size: usize,
+    pub used: usize,
+    pub peak: usize,
+    pub allocations: usize,
+    pub deallocations: usize,
+    pub failures: usize,
+}
+
+impl HeapStats {
+    pub const fn free(&self) -> usize {
+        self.size - self.used
+    }
+}
+
+/// Called with the requested Layout when an allocation cannot be satisfied
+pub type AllocFailHook = fn(Layout);
+
+/// Bounded-time allocator guarded by critical sections, so it can be shared by Tasks and Timers
+pub struct Heap {
+    tlsf: UnsafeCell<Tlsf>,
+    stats: Cell<HeapStats>,
+    hook: Cell<Option<AllocFailHook>>,
+    ready: Cell<bool>,
+}
+
+unsafe impl Sync for Heap {}
+
+impl Heap {
+    pub const fn new() -> Self {
+        Heap {
+            tlsf: UnsafeCell::new(Tlsf::new()),
+            stats: Cell::new(HeapStats { size: 0, used: 0, peak: 0, allocations: 0, deallocations: 0, failures: 0 }),
+            hook: Cell::new(None),
+            ready: Cell::new(false),
+        }
+    }
+
+    /// Gives the memory region [start, start + size) to the Heap.
+    /// It must be called once, before any allocation.
+    /// # Safety
+    /// The region must be valid, unused by anything else, and live forever.
+    pub unsafe fn init(&self, start: usize, size: usize) {
+        let cs = CritSect::activate();
+        self.setup(start, size);
+        cs.deactivate();
+    }
+
+    unsafe fn setup(&self, start: usize, size: usize) {
+        let begin = align_up(start, ALIGN);
+        let end = (start + size) & !(ALIGN - 1);
+        self.ready.set(true);
+
+        /* Room for a block and the ending sentinel */
+        if end < begin + MIN_BLOCK + HEADER {
+            return;
+        }
+
+        let block_size = (end - begin - HEADER).min(MAX_BLOCK);
+        let b = begin as *mut Block;
+        (*b).prev_phys = null_mut();
+        (*b).size = block_size;
+
+        /* Sentinel: a zero-sized used block that stops merges */
+        let sentinel = Block::next_phys(b);
+        (*sentinel).prev_phys = b;
+        (*sentinel).size = 0;
+
+        (*self.tlsf.get()).insert(b);
+
+        let mut stats = self.stats.get();
+        stats.size = block_size;
+        self.stats.set(stats);
+    }
+
+    /// Hook called when an allocation fails, i.e. to log or to reset the system
+    pub fn set_fail_hook(&self, hook: AllocFailHook) {
+        self.hook.set(Some(hook));
+    }
+
+    pub fn stats(&self) -> HeapStats {
+        let cs = CritSect::activate();
+        let stats = self.stats.get();
+        cs.deactivate();
+        stats
+    }
+
+    unsafe fn allocate(&self, layout: Layout) -> *mut u8 {
+        if !self.ready.get() {
+            let (start, size) = region();
+            self.setup(start, size);
+        }
+
+        let tlsf = &mut *self.tlsf.get();
+        let align = layout.align().max(ALIGN);
+        let size = (align_up(layout.size(), ALIGN) + HEADER).max(MIN_BLOCK);
+
+        /* Over-aligned requests need room to cut a leading free block */
+        let search = if align > ALIGN { size + align + MIN_BLOCK } else { size };
+        if search > MAX_BLOCK {
+            return null_mut();
+        }
+
+        let mut b = match tlsf.find(search) {
+            Some(b) => b,
+            None => return null_mut(),
+        };
+        tlsf.remove(b);
+
+        if align > ALIGN {
+            let payload = Block::payload(b) as usize;
+            let mut aligned = align_up(payload, align);
+            if aligned != payload && aligned - payload < MIN_BLOCK {
+                aligned = align_up(payload + MIN_BLOCK, align);
+            }
+            if aligned != payload {
+                let lead = b;
+                b = Block::split(lead, aligned - payload);
+                (*b).size &= !FREE;
+                tlsf.insert(lead);
+            }
+        }
+
+        if Block::size(b) - size >= MIN_BLOCK {
+            let rest = Block::split(b, size);
+            tlsf.release(rest);
+        }
+
+        let mut stats = self.stats.get();
+        stats.used += Block::size(b);
+        stats.peak = stats.peak.max(stats.used);
+        stats.allocations += 1;
+        self.stats.set(stats);
+
+        Block::payload(b)
+    }
+
+    unsafe fn deallocate(&self, ptr: *mut u8) {
+        let b = Block::from_payload(ptr);
+
+        let mut stats = self.stats.get();
+        stats.used -= Block::size(b);
+        stats.deallocations += 1;
+        self.stats.set(stats);
+
+        (*self.tlsf.get()).release(b);
+    }
+}
+
+impl Default for Heap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+unsafe impl GlobalAlloc for Heap {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let cs = CritSect::activate();
+        let ptr = self.allocate(layout);
+
+        if ptr.is_null() {
+            let mut stats = self.stats.get();
+            stats.failures += 1;
+            self.stats.set(stats);
+            cs.deactivate();
+
+            if let Some(hook) = self.hook.get() {
+                hook(layout);
+            }
+            return ptr;
+        }
+
+        cs.deactivate();
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, _layout: Layout) {
+        let cs = CritSect::activate();
+        self.deallocate(ptr);
+        cs.deactivate();
+    }
+}
+
+//*********************************************************************************************************************
+// HEAP REGION
+//*********************************************************************************************************************
+
+#[cfg(not(native))]
+extern "C" {
+    static mut ld_heap_start: u8;
+    static mut ld_heap_end: u8;
+}
+
+/// RAM left by the linker, from the end of .noinit to the end of RAM
+#[cfg(not(native))]
+fn region() -> (usize, usize) {
+    let start = core::ptr::addr_of_mut!(ld_heap_start) as usize;
+    let end = core::ptr::addr_of_mut!(ld_heap_end) as usize;
+    (start, end.saturating_sub(start))
+}
+
+#[cfg(native)]
+const NATIVE_HEAP_SIZE: usize = 64 * 1024;
+
+#[cfg(native)]
+static mut NATIVE_HEAP: [usize; NATIVE_HEAP_SIZE / size_of::<usize>()] = [0; NATIVE_HEAP_SIZE / size_of::<usize>()];
+
+/// Host simulation has no linker region: a static buffer is used instead
+#[cfg(native)]
+fn region() -> (usize, usize) {
+    (core::ptr::addr_of_mut!(NATIVE_HEAP) as usize, NATIVE_HEAP_SIZE)
+}
+
+/// System heap: global allocator on targets, so that alloc::Vec and alloc::Box can be used by Tasks
+#[cfg_attr(not(native), global_allocator)]
+pub static HEAP: Heap = Heap::new();
@@ -194,6 +194,79 @@ impl<const ADR: usize, const OFF: usize> WO<ADR, OFF> {
 }
 
 
+/// Byte-wide register, for peripherals that pack 8-bit registers one after another (eg: 16550 UARTs)
+pub(crate) struct RO8<const ADR: usize, const OFF: usize>;
+
+impl<const ADR: usize, const OFF: usize> RO8<ADR, OFF> {
+    const PTR: *mut u8 = (ADR + OFF) as *mut u8;
+
+    pub const fn new() -> Self {
+        Self
+    }
+
+    pub const fn addr(&self) -> usize {
+        ADR + OFF
+    }
+
+    #[inline(always)]
+    pub fn read(&self) -> u8 {
+        unsafe { Self::PTR.read_volatile() }
+    }
+
+    #[inline(always)]
+    pub fn check(&self, mask: u8) -> bool {
+        let r = self.read();
+        (r & mask) != 0
+    }
+}
+
+
+/// Byte-wide register, for peripherals that pack 8-bit registers one after another (eg: 16550 UARTs)
+pub(crate) struct RW8<const ADR: usize, const OFF: usize>;
+
+impl<const ADR: usize, const OFF: usize> RW8<ADR, OFF> {
+    const PTR: *mut u8 = (ADR + OFF) as *mut u8;
+
+    pub const fn new() -> Self {
+        Self
+    }
+
+    pub const fn addr(&self) -> usize {
+        ADR + OFF
+    }
+
+    #[inline(always)]
+    pub fn read(&self) -> u8 {
+        unsafe { Self::PTR.read_volatile() }
+    }
+
+    #[inline(always)]
+    pub fn check(&self, mask: u8) -> bool {
+        let r = self.read();
+        (r & mask) != 0
+    }
+
+    #[inline(always)]
+    pub fn write(&self, val: u8) {
+        unsafe {
+            Self::PTR.write_volatile(val);
+        }
+    }
+
+    #[inline(always)]
+    pub fn set(&self, mask: u8) {
+        let r = self.read();
+        self.write(r | mask);
+    }
+
+    #[inline(always)]
+    pub fn clear(&self, mask: u8) {
+        let r = self.read();
+        self.write(r & !mask);
+    }
+}
+
+
 pub(crate) struct RWArea<const ADR: usize, const OFF: usize, const WORDS: usize>;
 
 impl<const ADR: usize, const OFF: usize, const WORDS: usize> RWArea<ADR, OFF, WORDS> {
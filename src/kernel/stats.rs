@@ -0,0 +1,174 @@
+//! RusTOS - Rust Real Time Operating System
+//! Copyright (C) 2025 - Fabio Zanin - fabio.zanin93@outlook.com
+//!
+//! This program is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License.
+//!
+//! This program is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+//!
+//! You should have received a copy of the GNU General Public License
+//! along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//!
+//! ********************************************** CPU STATISTICS OF RusTOS *******************************************
+//!
+//! Run time of every Task, measured with a free-running time base at each context switch:
+//! core cycles of the DWT on Cortex-M, CLINT mtime on RISC-V, microseconds on the host simulation.
+//! The time base is 32 bits wide: time is charged at least once per sub-window, so it never wraps in between.
+//!
+//! CPU load is computed over a sliding window of STATS_SLOTS sub-windows of SLOT_TICKS ticks each:
+//! the sub-window being filled is not counted, so load moves forward a sub-window at a time.
+//!
+//! *******************************************************************************************************************
+
+use crate::bitvec::BitVec;
+use crate::kernel::{Ticks, IDLE_PRIO};
+
+/// Complete sub-windows the CPU load is computed on
+pub const STATS_SLOTS: usize = 4;
+
+/// Ticks of a sub-window
+pub const SLOT_TICKS: Ticks = 250;
+
+/// Row of the idle Task in the sub-windows, after the Task slots
+const IDLE_ROW: usize = BitVec::BITS;
+
+/// Snapshot of CPU statistics. Loads are in hundredths of percent (10000 is 100%),
+/// times are in counts of the time base.
+#[derive(Clone, Copy, Debug)]
+pub struct CpuStats {
+    /// Context switches since start
+    pub switches: usize,
+    /// Context switches that took the CPU from a Task still ready to run
+    pub preemptions: usize,
+    /// Time spent by the idle Task since start
+    pub idle_time: u64,
+    /// CPU load of the idle Task over the window
+    pub idle_load: u16,
+    /// CPU load of each Task over the window, indexed by Task id
+    pub load: [u16; BitVec::BITS],
+}
+
+pub(crate) struct Stats {
+    /// Time base when time was last charged to the running Task
+    since: u32,
+    /// Run time of each Task since it has been added
+    run_time: [u64; BitVec::BITS],
+    idle_time: u64,
+    /// Run time of each Task in each sub-window, idle Task in the last row
+    window: [[u32; STATS_SLOTS + 1]; BitVec::BITS + 1],
+    /// Total time of each sub-window
+    elapsed: [u32; STATS_SLOTS + 1],
+    /// Sub-window being filled
+    current: usize,
+    /// Ticks counted in the current sub-window
+    ticks: Ticks,
+    switches: usize,
+    preemptions: usize,
+}
+
+impl Stats {
+    pub const fn new() -> Self {
+        Self {
+            since: 0,
+            run_time: [0; BitVec::BITS],
+            idle_time: 0,
+            window: [[0; STATS_SLOTS + 1]; BitVec::BITS + 1],
+            elapsed: [0; STATS_SLOTS + 1],
+            current: 0,
+            ticks: 0,
+            switches: 0,
+            preemptions: 0,
+        }
+    }
+
+    /// Charges the time since last call to the running Task
+    pub fn charge(&mut self, running: usize, now: u32) {
+        let time = now.wrapping_sub(self.since);
+        self.since = now;
+
+        let row = if running == IDLE_PRIO {
+            self.idle_time += time as u64;
+            IDLE_ROW
+        } else {
+            self.run_time[running] += time as u64;
+            running
+        };
+
+        self.window[row][self.current] = self.window[row][self.current].saturating_add(time);
+        self.elapsed[self.current] = self.elapsed[self.current].saturating_add(time);
+    }
+
+    /// Running Task is switched out, preempted if it is still ready
+    pub fn switch(&mut self, running: usize, preempted: bool, now: u32) {
+        self.charge(running, now);
+        self.switches += 1;
+        if preempted {
+            self.preemptions += 1;
+        }
+    }
+
+    /// Counts elapsed ticks, moving the window forward at the end of each sub-window
+    pub fn tick(&mut self, running: usize, ticks: Ticks, now: u32) {
+        self.ticks += ticks;
+        if self.ticks < SLOT_TICKS {
+            return;
+        }
+
+        self.charge(running, now);
+        let slots = (self.ticks / SLOT_TICKS).min(STATS_SLOTS as Ticks + 1);
+        self.ticks %= SLOT_TICKS;
+
+        for _ in 0..slots {
+            self.current = (self.current + 1) % (STATS_SLOTS + 1);
+            for row in self.window.iter_mut() {
+                row[self.current] = 0;
+            }
+            self.elapsed[self.current] = 0;
+        }
+    }
+
+    /// A new Task takes the slot: time of the previous one is forgotten
+    pub const fn clear(&mut self, id: usize) {
+        self.run_time[id] = 0;
+        self.window[id] = [0; STATS_SLOTS + 1];
+    }
+
+    /// Run time of the Task in the slot since it has been added
+    pub fn run_time(&self, id: usize) -> u64 {
+        self.run_time[id]
+    }
+
+    pub fn snapshot(&self) -> CpuStats {
+        let total: u64 = self.complete(&self.elapsed);
+        let load = |row: &[u32; STATS_SLOTS + 1]| match total {
+            0 => 0,
+            total => (self.complete(row) * 10_000 / total) as u16,
+        };
+
+        let mut stats = CpuStats {
+            switches: self.switches,
+            preemptions: self.preemptions,
+            idle_time: self.idle_time,
+            idle_load: load(&self.window[IDLE_ROW]),
+            load: [0; BitVec::BITS],
+        };
+        for (id, row) in self.window[..BitVec::BITS].iter().enumerate() {
+            stats.load[id] = load(row);
+        }
+
+        stats
+    }
+
+    /// Sum of the complete sub-windows
+    fn complete(&self, row: &[u32; STATS_SLOTS + 1]) -> u64 {
+        row.iter()
+            .enumerate()
+            .filter(|&(slot, _)| slot != self.current)
+            .map(|(_, &time)| time as u64)
+            .sum()
+    }
+}
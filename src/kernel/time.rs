@@ -16,7 +16,7 @@
 use core::cmp::Ordering;
 use core::ops::{Add, AddAssign, Div, Mul, Sub, SubAssign};
 
-use crate::kernel::{SystemTicks, KERNEL};
+use crate::kernel::{SystemTicks, Task, KERNEL};
 
 use super::Ticks;
 
@@ -31,6 +31,16 @@ impl Instant {
         KERNEL.with(|_, k| t = k.ticks);
         Instant { t }
     }
+
+    pub const fn from_ticks(t: SystemTicks) -> Instant {
+        Instant { t }
+    }
+
+    /// System ticks since the scheduler started
+    #[inline]
+    pub const fn ticks(&self) -> SystemTicks {
+        self.t
+    }
 }
 
 impl Add<Duration> for Instant {
@@ -52,9 +62,10 @@ impl AddAssign<Duration> for Instant {
 impl Sub for Instant {
     type Output = Duration;
 
+    /// Saturates to the longest Duration, instead of wrapping
     fn sub(self, rhs: Self) -> Self::Output {
         Duration {
-            t: self.t.saturating_sub(rhs.t) as u32,
+            t: self.t.saturating_sub(rhs.t).min(Ticks::MAX as SystemTicks) as Ticks,
         }
     }
 }
@@ -160,12 +171,63 @@ impl Deadline {
     }
 
     pub fn is_expired(&self) -> bool {
-        self.deadline >= Instant::now()
+        Instant::now() >= self.deadline
     }
 
+    /// Next deadline is a period after the previous one, not after now: deadlines don't drift
     pub fn reload(&mut self) {
         self.deadline += self.period;
     }
+
+    pub fn deadline(&self) -> Instant {
+        self.deadline
+    }
+}
+
+/// Wakes a Task at exact multiples of a period, counted from when the Periodic is created:
+/// execution time of the loop doesn't accumulate as drift.
+pub struct Periodic {
+    deadline: Deadline,
+    overruns: usize,
+}
+
+impl Periodic {
+    pub fn new(period: Duration) -> Self {
+        Self {
+            deadline: Deadline::new(period),
+            overruns: 0,
+        }
+    }
+
+    /// Sleeps till the next period. If the Task is late the period is overrun: it returns at once with the delay,
+    /// and whole periods already passed are skipped, so that following wake-ups stay on the same cadence.
+    pub fn wait(&mut self, task: &mut Task) -> Result<(), Duration> {
+        let res = task.sleep_until(self.deadline.deadline);
+        self.deadline.reload();
+
+        if res.is_err() {
+            let now = Instant::now().t;
+            let period = self.deadline.period.t as SystemTicks;
+            let next = self.deadline.deadline.t;
+            if period != 0 && next <= now {
+                let skipped = (now - next) / period + 1;
+                self.deadline.deadline.t = next.saturating_add(skipped * period);
+            }
+            self.overruns += 1;
+        }
+
+        res
+    }
+
+    /// Instant of the next wake-up
+    pub fn next(&self) -> Instant {
+        self.deadline.deadline
+    }
+
+    /// Periods overrun since creation
+    pub fn overruns(&self) -> usize {
+        self.overruns
+    }
 }
 
 pub struct MHz(usize);
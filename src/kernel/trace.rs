@@ -0,0 +1,210 @@
+//! RusTOS - Rust Real Time Operating System
+//! Copyright (C) 2025 - Fabio Zanin - fabio.zanin93@outlook.com
+//!
+//! This program is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License.
+//!
+//! This program is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+//!
+//! You should have received a copy of the GNU General Public License
+//! along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//!
+//! ********************************************* TRACE RECORDER OF RusTOS ********************************************
+//!
+//! Kernel events are recorded as 12 bytes timestamped records into a RAM ring buffer of TRACE_EVENTS records:
+//! when it is full the oldest records are overwritten, so it always holds the last events before a problem.
+//! A slot is reserved with an atomic increment, then written: records come from the Kernel and from ISRs, which
+//! complete their records before the interrupted code goes on, so no lock is needed.
+//! Timestamps are read from the time base used by CPU statistics (DWT cycles, CLINT mtime, host microseconds).
+//!
+//! export() writes the buffer, oldest record first, to any SerialStream. All values are little endian.
+//!
+//! Header (16 bytes):
+//! | offset | size | field                                               |
+//! |--------|------|-----------------------------------------------------|
+//! | 0      | 4    | magic "RTRC"                                        |
+//! | 4      | 1    | format version, 1                                   |
+//! | 5      | 1    | record size, 12                                     |
+//! | 6      | 2    | number of records that follow                       |
+//! | 8      | 4    | time base frequency, in Hz                          |
+//! | 12     | 4    | records overwritten before the export               |
+//!
+//! Record (12 bytes):
+//! | offset | size | field                                               |
+//! |--------|------|-----------------------------------------------------|
+//! | 0      | 4    | timestamp, wrapping 32 bits time base               |
+//! | 4      | 1    | event, see TraceEvent                               |
+//! | 5      | 1    | Task id, 255 for the idle Task or no Task           |
+//! | 6      | 2    | code, depends on the event                          |
+//! | 8      | 4    | argument, depends on the event                      |
+//!
+//! *******************************************************************************************************************
+
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use crate::drivers::serial::SerialStream;
+use crate::kernel::{CorePeripherals, Kernel, IDLE_PRIO};
+
+/// Records kept in the ring buffer, must be a power of two: STM32 boards have 10K of RAM only
+#[cfg(not(feature = "stm32"))]
+pub const TRACE_EVENTS: usize = 256;
+#[cfg(feature = "stm32")]
+pub const TRACE_EVENTS: usize = 64;
+
+const TRACE_MAGIC: [u8; 4] = *b"RTRC";
+const TRACE_VERSION: u8 = 1;
+
+/// Kinds of recorded events, with the meaning of their code and argument
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum TraceEvent {
+    /// Task gets the CPU; argument is the id of the Task switched out
+    TaskSwitch = 0,
+    /// SysCall served by the Kernel; code is the SysCall number
+    SysCall = 1,
+    /// Task blocks on a Semaphore; argument is the Semaphore address.
+    /// Queues, Stream Buffers and Memory Pools block on Semaphores inside them.
+    Block = 2,
+    /// Task waiting on a Semaphore is woken up; code is 0 if released, 1 on timeout; argument is the Semaphore address
+    Unblock = 3,
+    /// Software Timer fires; code is the Timer slot, argument is the callback address
+    TimerFire = 4,
+    /// ISR starts; code is the interrupt number
+    IsrEnter = 5,
+    /// ISR ends; code is the interrupt number
+    IsrExit = 6,
+    /// Recorded by the application with user(); code and argument are free
+    User = 7,
+}
+
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct TraceRecord {
+    pub time: u32,
+    pub event: u8,
+    pub task: u8,
+    pub code: u16,
+    pub arg: u32,
+}
+
+impl TraceRecord {
+    const fn new() -> Self {
+        Self {
+            time: 0,
+            event: 0,
+            task: 0,
+            code: 0,
+            arg: 0,
+        }
+    }
+
+    fn to_bytes(self) -> [u8; 12] {
+        let mut bytes = [0; 12];
+        bytes[0..4].copy_from_slice(&self.time.to_le_bytes());
+        bytes[4] = self.event;
+        bytes[5] = self.task;
+        bytes[6..8].copy_from_slice(&self.code.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.arg.to_le_bytes());
+        bytes
+    }
+}
+
+struct TraceBuffer {
+    records: [UnsafeCell<TraceRecord>; TRACE_EVENTS],
+    /// Records written since last clear, the slot is its value modulo TRACE_EVENTS
+    head: AtomicUsize,
+    enabled: AtomicBool,
+    /// Bit mask of the recorded TraceEvents
+    filter: AtomicUsize,
+}
+
+/// Slots are reserved atomically and each one is written by a single writer
+unsafe impl Sync for TraceBuffer {}
+
+static TRACE: TraceBuffer = TraceBuffer {
+    records: [const { UnsafeCell::new(TraceRecord::new()) }; TRACE_EVENTS],
+    head: AtomicUsize::new(0),
+    enabled: AtomicBool::new(true),
+    filter: AtomicUsize::new(usize::MAX),
+};
+
+const _: () = assert!(TRACE_EVENTS.is_power_of_two(), "TRACE_EVENTS must be a power of two");
+
+/// Records an event, if recording is enabled and the event is not filtered out
+pub(crate) fn record(event: TraceEvent, task: usize, code: usize, arg: usize) {
+    if !TRACE.enabled.load(Ordering::Relaxed) || TRACE.filter.load(Ordering::Relaxed) & (1 << event as usize) == 0 {
+        return;
+    }
+
+    let slot = TRACE.head.fetch_add(1, Ordering::Relaxed) % TRACE_EVENTS;
+    let record = TraceRecord {
+        time: CorePeripherals::cycles(),
+        event: event as u8,
+        task: task.min(IDLE_PRIO) as u8,
+        code: code as u16,
+        arg: arg as u32,
+    };
+    unsafe { TRACE.records[slot].get().write_volatile(record) };
+}
+
+/// To be called first thing in an ISR
+pub fn isr_enter(irq: usize) {
+    record(TraceEvent::IsrEnter, Kernel::running_id(), irq, 0);
+}
+
+/// To be called last thing in an ISR
+pub fn isr_exit(irq: usize) {
+    record(TraceEvent::IsrExit, Kernel::running_id(), irq, 0);
+}
+
+/// Records an application event, i.e. to mark the start and the end of a computation
+pub fn user(code: u16, arg: u32) {
+    record(TraceEvent::User, Kernel::running_id(), code as usize, arg as usize);
+}
+
+/// Starts or stops recording, i.e. to freeze the buffer when a problem is detected
+pub fn enable(enabled: bool) {
+    TRACE.enabled.store(enabled, Ordering::Relaxed);
+}
+
+/// Records only the given events, all of them are recorded at start
+pub fn set_filter(events: &[TraceEvent]) {
+    let mask = events.iter().fold(0, |mask, &event| mask | 1 << event as usize);
+    TRACE.filter.store(mask, Ordering::Relaxed);
+}
+
+/// Discards all records
+pub fn clear() {
+    TRACE.head.store(0, Ordering::Relaxed);
+}
+
+/// Writes header and records to the port, then clears the buffer. Recording is paused meanwhile.
+pub fn export<S: SerialStream + ?Sized>(port: &mut S) {
+    let enabled = TRACE.enabled.swap(false, Ordering::Relaxed);
+
+    let written = TRACE.head.load(Ordering::Relaxed);
+    let count = written.min(TRACE_EVENTS);
+    let lost = written - count;
+
+    let mut header = [0; 16];
+    header[0..4].copy_from_slice(&TRACE_MAGIC);
+    header[4] = TRACE_VERSION;
+    header[5] = size_of::<TraceRecord>() as u8;
+    header[6..8].copy_from_slice(&(count as u16).to_le_bytes());
+    header[8..12].copy_from_slice(&CorePeripherals::cycles_frequency().to_le_bytes());
+    header[12..16].copy_from_slice(&(lost as u32).to_le_bytes());
+    port.send(&header);
+
+    for id in written - count..written {
+        let record = unsafe { TRACE.records[id % TRACE_EVENTS].get().read_volatile() };
+        port.send(&record.to_bytes());
+    }
+
+    clear();
+    TRACE.enabled.store(enabled, Ordering::Relaxed);
+}
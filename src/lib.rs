@@ -18,6 +18,13 @@
 #![no_main]
 #![allow(dead_code)]
 
+/// Host simulation port runs Tasks on OS threads
+#[cfg(native)]
+extern crate std;
+
+/// Vec, Box and friends are available with the kernel heap
+#[cfg(feature = "heap")]
+extern crate alloc;
 
 pub mod bitvec;
 pub mod kernel;
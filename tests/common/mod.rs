@@ -0,0 +1,66 @@
+//! RusTOS - Rust Real Time Operating System 
+//! Copyright (C) 2025 - Fabio Zanin - fabio.zanin93@outlook.com
+//! 
+//! This program is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License.
+//! 
+//! This program is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+//! 
+//! You should have received a copy of the GNU General Public License
+//! along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//! 
+//! Helpers of host tests. Kernel is a static, so every test file is a process with its own Kernel:
+//! Tasks record what they see, the test thread waits for them and checks the records.
+
+#![allow(dead_code)]
+
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use RusTOS::kernel::*;
+
+/// Adds Tasks and Timers in setup, then starts the scheduler on a new thread, that becomes SysTick
+pub fn start(setup: fn(&mut Kernel)) {
+    thread::spawn(move || {
+        let cs = CritSect::activate();
+        let k = KERNEL.access(&cs);
+        setup(k);
+        k.init(cs);
+    });
+}
+
+/// Events recorded by Tasks, in order
+pub struct Log(Mutex<Vec<String>>);
+
+impl Log {
+    pub const fn new() -> Self {
+        Self(Mutex::new(Vec::new()))
+    }
+
+    pub fn push(&self, event: impl Into<String>) {
+        self.0.lock().unwrap().push(event.into());
+    }
+
+    /// Waits till Tasks have recorded count events, then returns them
+    pub fn wait(&self, count: usize) -> Vec<String> {
+        for _ in 0..500 {
+            let log = self.0.lock().unwrap();
+            if log.len() >= count {
+                return log.clone();
+            }
+            drop(log);
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        panic!("Tasks recorded only {:?}", self.0.lock().unwrap());
+    }
+}
+
+pub fn now() -> SystemTicks {
+    Instant::now().ticks()
+}
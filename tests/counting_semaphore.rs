@@ -0,0 +1,75 @@
+//! RusTOS - Rust Real Time Operating System 
+//! Copyright (C) 2025 - Fabio Zanin - fabio.zanin93@outlook.com
+//! 
+//! This program is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License.
+//! 
+//! This program is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+//! 
+//! You should have received a copy of the GNU General Public License
+//! along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+mod common;
+
+use common::{now, start, Log};
+use RusTOS::kernel::*;
+
+static TAKER_STACK: Stack::<256> = Stack::new();
+static GIVER_STACK: Stack::<256> = Stack::new();
+
+static TAKER_TASK: Task = Task::new(taker, 2, &TAKER_STACK);
+static GIVER_TASK: Task = Task::new(giver, 1, &GIVER_STACK);
+
+/// Initial count above the maximum is clamped to it
+static SEMAPHORE: CountingSemaphore = CountingSemaphore::new(5, 3);
+static LOG: Log = Log::new();
+
+fn taker(task: &mut Task) -> ! {
+    LOG.push(format!("initial {}", SEMAPHORE.count()));
+    let taken = (0..4).filter(|_| SEMAPHORE.try_take().is_ok()).count();
+    LOG.push(format!("taken {} {:?}", taken, SEMAPHORE.try_take()));
+
+    let start = now();
+    let res = SEMAPHORE.take_timeout(task, ms::new(10));
+    LOG.push(format!("timeout {:?} {}", res, now() - start >= 10));
+
+    // Woken up by the giver, the token is handed over instead of being counted
+    SEMAPHORE.take(task);
+    LOG.push(format!("given {}", SEMAPHORE.count()));
+
+    loop {
+        task.sleep(1000);
+    }
+}
+
+fn giver(task: &mut Task) -> ! {
+    task.sleep(20);
+    let _ = SEMAPHORE.give();
+    let given = (0..4).filter(|_| SEMAPHORE.give().is_ok()).count();
+    LOG.push(format!("counted {} {:?}", given, SEMAPHORE.give()));
+
+    loop {
+        task.sleep(1000);
+    }
+}
+
+/// Tokens are taken down to zero, then a wait times out or gets a given token; gives stop at the maximum
+#[test]
+fn counting_semaphore_counts_to_max() {
+    start(|k| {
+        k.add_task(&GIVER_TASK).unwrap();
+        k.add_task(&TAKER_TASK).unwrap();
+    });
+
+    assert_eq!(LOG.wait(5), [
+        "initial 3",
+        "taken 3 Err(WouldBlock)",
+        "timeout Err(Timeout) true",
+        "given 0",
+        "counted 3 Err(Full)",
+    ]);
+}
@@ -0,0 +1,90 @@
+//! RusTOS - Rust Real Time Operating System 
+//! Copyright (C) 2025 - Fabio Zanin - fabio.zanin93@outlook.com
+//! 
+//! This program is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License.
+//! 
+//! This program is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+//! 
+//! You should have received a copy of the GNU General Public License
+//! along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+mod common;
+
+use common::{now, start, Log};
+use RusTOS::kernel::*;
+use RusTOS::bitvec::BitVec;
+
+static ANY_STACK: Stack::<256> = Stack::new();
+static ALL_STACK: Stack::<256> = Stack::new();
+static SETTER_STACK: Stack::<256> = Stack::new();
+
+static ANY_TASK: Task = Task::new(any, 3, &ANY_STACK);
+static ALL_TASK: Task = Task::new(all, 2, &ALL_STACK);
+static SETTER_TASK: Task = Task::new(setter, 1, &SETTER_STACK);
+
+static EVENTS: EventGroup = EventGroup::new();
+static LOG: Log = Log::new();
+
+fn any(task: &mut Task) -> ! {
+    let matched = EVENTS.wait(task, BitVec::init(0b011), EventWait::Any, true);
+    LOG.push(format!("any {:#b}", matched.raw()));
+
+    loop {
+        task.sleep(1000);
+    }
+}
+
+fn all(task: &mut Task) -> ! {
+    let mask = BitVec::init(0b110);
+    let res = EVENTS.wait_timeout(task, mask, EventWait::All, false, ms::new(0)).map(|flags| flags.raw());
+    LOG.push(format!("all now {:?}", res));
+
+    let start = now();
+    let res = EVENTS.wait_timeout(task, mask, EventWait::All, false, ms::new(10)).map(|flags| flags.raw());
+    LOG.push(format!("all timeout {:?} {}", res, now() - start >= 10));
+
+    let matched = EVENTS.wait(task, mask, EventWait::All, false);
+    LOG.push(format!("all {:#b}", matched.raw()));
+
+    loop {
+        task.sleep(1000);
+    }
+}
+
+fn setter(task: &mut Task) -> ! {
+    task.sleep(20);
+    EVENTS.set(BitVec::init(0b100));
+    LOG.push(format!("set {:#b}", EVENTS.get().raw()));
+    // Both waits are satisfied by the same flags, only the Any one clears its match
+    EVENTS.set(BitVec::init(0b010));
+    LOG.push(format!("left {:#b}", EVENTS.get().raw()));
+
+    loop {
+        task.sleep(1000);
+    }
+}
+
+/// Any and All waits are woken up only when their condition holds, clearing on exit only the matched flags
+/// of the Task that asked for it; a wait whose condition doesn't hold fails at once or after its timeout.
+#[test]
+fn event_group_any_all_clear_timeout() {
+    start(|k| {
+        k.add_task(&SETTER_TASK).unwrap();
+        k.add_task(&ALL_TASK).unwrap();
+        k.add_task(&ANY_TASK).unwrap();
+    });
+
+    assert_eq!(LOG.wait(6), [
+        "all now Err(WouldBlock)",
+        "all timeout Err(Timeout) true",
+        "set 0b100",
+        "any 0b10",
+        "all 0b110",
+        "left 0b100",
+    ]);
+}
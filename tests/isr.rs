@@ -0,0 +1,80 @@
+//! RusTOS - Rust Real Time Operating System 
+//! Copyright (C) 2025 - Fabio Zanin - fabio.zanin93@outlook.com
+//! 
+//! This program is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License.
+//! 
+//! This program is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+//! 
+//! You should have received a copy of the GNU General Public License
+//! along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+mod common;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use common::{start, Log};
+use RusTOS::kernel::*;
+use RusTOS::bitvec::BitVec;
+
+static RECEIVER_STACK: Stack::<256> = Stack::new();
+static RECEIVER_TASK: Task = Task::new(receiver, 2, &RECEIVER_STACK);
+
+static QUEUE: Queue<u32, 2> = Queue::new();
+static TOKENS: CountingSemaphore = CountingSemaphore::new(0, 1);
+static EVENTS: EventGroup = EventGroup::new();
+static WOKEN: AtomicBool = AtomicBool::new(false);
+static LOG: Log = Log::new();
+
+/// Timers fire in SysTick, thus they use the ISR API: every object is updated, then the context switch
+/// is requested once at the end
+fn interrupt() {
+    let mut woken = false;
+    let _ = QUEUE.push_from_isr(7, &mut woken);
+    let _ = TOKENS.give_from_isr(&mut woken);
+    EVENTS.set_from_isr(BitVec::init(0b1), &mut woken);
+    let _ = notify_from_isr(&RECEIVER_TASK, 5, NotifyAction::Set, &mut woken);
+    WOKEN.store(woken, Ordering::Relaxed);
+    yield_from_isr(woken);
+}
+
+fn receiver(task: &mut Task) -> ! {
+    let value = QUEUE.pop(task);
+    LOG.push(format!("pop {} woken {}", value, WOKEN.load(Ordering::Relaxed)));
+
+    // Everything else has been given by the same ISR run, so nothing blocks
+    TOKENS.take(task);
+    LOG.push(format!("tokens {}", TOKENS.count()));
+    let flags = EVENTS.wait(task, BitVec::init(0b1), EventWait::Any, true);
+    LOG.push(format!("events {:#b}", flags.raw()));
+    LOG.push(format!("notified {:?}", task.wait_notification(0, ms::new(1))));
+
+    let mut woken = false;
+    LOG.push(format!("empty {:?} {}", QUEUE.pop_from_isr(&mut woken), woken));
+
+    loop {
+        task.sleep(1000);
+    }
+}
+
+/// *_from_isr calls from an ISR update Queues, Semaphores, EventGroups and notifications without SysCalls,
+/// and tell through woken that a waiting Task has to preempt the running one.
+#[test]
+fn isr_variants_wake_tasks() {
+    start(|k| {
+        k.add_task(&RECEIVER_TASK).unwrap();
+        let _ = k.new_timer(Duration::new(10), interrupt, true, TimerMode::OneShot).unwrap();
+    });
+
+    assert_eq!(LOG.wait(5), [
+        "pop 7 woken true",
+        "tokens 0",
+        "events 0b1",
+        "notified Ok(5)",
+        "empty None false",
+    ]);
+}
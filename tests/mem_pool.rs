@@ -0,0 +1,101 @@
+//! RusTOS - Rust Real Time Operating System 
+//! Copyright (C) 2025 - Fabio Zanin - fabio.zanin93@outlook.com
+//! 
+//! This program is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License.
+//! 
+//! This program is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+//! 
+//! You should have received a copy of the GNU General Public License
+//! along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use common::{now, start, Log};
+use RusTOS::kernel::*;
+
+static OWNER_STACK: Stack::<256> = Stack::new();
+static FREER_STACK: Stack::<256> = Stack::new();
+
+static OWNER_TASK: Task = Task::new(owner, 2, &OWNER_STACK);
+static FREER_TASK: Task = Task::new(freer, 1, &FREER_STACK);
+
+#[derive(Debug)]
+struct Frame(u32);
+
+static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+impl Drop for Frame {
+    fn drop(&mut self) {
+        DROPS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+static POOL: MemPool<Frame, 2> = MemPool::new();
+/// Blocks are passed to the other Task as raw pointers
+static BLOCKS: Queue<usize, 1> = Queue::new();
+static LOG: Log = Log::new();
+
+fn drops() -> usize {
+    DROPS.load(Ordering::Relaxed)
+}
+
+fn owner(task: &mut Task) -> ! {
+    let first = POOL.try_alloc(Frame(1)).unwrap();
+    let second = POOL.try_alloc(Frame(2)).unwrap();
+    LOG.push(format!("available {}", POOL.available()));
+    LOG.push(format!("full {:?}", POOL.try_alloc(Frame(3)).err().map(|frame| frame.0)));
+
+    let start = now();
+    let res = POOL.alloc_timeout(task, Frame(4), ms::new(10)).err().map(|frame| frame.0);
+    LOG.push(format!("timeout {:?} {}", res, now() - start >= 10));
+
+    BLOCKS.push(task, second.into_raw() as usize);
+    let third = POOL.alloc(task, Frame(5));
+    LOG.push(format!("allocated {} drops {}", third.0, drops()));
+
+    drop(first);
+    drop(third);
+    LOG.push(format!("available {} drops {}", POOL.available(), drops()));
+
+    loop {
+        task.sleep(1000);
+    }
+}
+
+fn freer(task: &mut Task) -> ! {
+    let ptr = BLOCKS.pop(task) as *mut Frame;
+    task.sleep(10);
+    let block = unsafe { POOL.from_raw(ptr) };
+    LOG.push(format!("freeing {}", block.0));
+    drop(block);
+
+    loop {
+        task.sleep(1000);
+    }
+}
+
+/// An exhausted pool gives values back or makes the Task wait, till a PoolBox is dropped:
+/// its value is dropped and its block reused, even when moved to another Task as a raw pointer.
+#[test]
+fn pool_blocks_till_a_box_is_dropped() {
+    start(|k| {
+        k.add_task(&FREER_TASK).unwrap();
+        k.add_task(&OWNER_TASK).unwrap();
+    });
+
+    assert_eq!(LOG.wait(6), [
+        "available 0",
+        "full Some(3)",
+        "timeout Some(4) true",
+        "freeing 2",
+        "allocated 5 drops 3",
+        "available 2 drops 5",
+    ]);
+}
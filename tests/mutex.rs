@@ -0,0 +1,89 @@
+//! RusTOS - Rust Real Time Operating System 
+//! Copyright (C) 2025 - Fabio Zanin - fabio.zanin93@outlook.com
+//! 
+//! This program is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License.
+//! 
+//! This program is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+//! 
+//! You should have received a copy of the GNU General Public License
+//! along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+mod common;
+
+use common::{now, start, Log};
+use RusTOS::kernel::*;
+
+static HIGH_STACK: Stack::<256> = Stack::new();
+static MID_STACK: Stack::<256> = Stack::new();
+static LOW_STACK: Stack::<256> = Stack::new();
+
+static HIGH_TASK: Task = Task::new(high, 3, &HIGH_STACK);
+static MID_TASK: Task = Task::new(mid, 2, &MID_STACK);
+static LOW_TASK: Task = Task::new(low, 1, &LOW_STACK);
+
+static MUTEX: Mutex<u32> = Mutex::new(0);
+static LOG: Log = Log::new();
+
+/// Keeps the core busy till the tick, reading the time lets the Task be preempted meanwhile
+fn spin_until(tick: SystemTicks) {
+    while now() < tick {}
+}
+
+fn high(task: &mut Task) -> ! {
+    task.sleep(5);
+    LOG.push("high waits");
+    let value = *MUTEX.lock(task).unwrap();
+    LOG.push(format!("high locked {}", value));
+
+    loop {
+        task.sleep(1000);
+    }
+}
+
+fn mid(task: &mut Task) -> ! {
+    task.sleep(10);
+    LOG.push("mid runs");
+    spin_until(now() + 10);
+
+    loop {
+        task.sleep(1000);
+    }
+}
+
+fn low(task: &mut Task) -> ! {
+    let mut guard = MUTEX.lock(task).unwrap();
+    // Mid priority Task is ready from tick 10, but the owner has inherited the priority of the waiting one
+    spin_until(20);
+    *guard = 42;
+    LOG.push("low unlocks");
+    drop(guard);
+    LOG.push("low done");
+
+    loop {
+        task.sleep(1000);
+    }
+}
+
+/// The owner of a Mutex runs with the priority of the Task waiting for it, so a mid priority Task
+/// can't delay the high priority one; on unlock the Mutex is handed over and the owner priority restored.
+#[test]
+fn owner_inherits_waiter_priority() {
+    start(|k| {
+        k.add_task(&LOW_TASK).unwrap();
+        k.add_task(&MID_TASK).unwrap();
+        k.add_task(&HIGH_TASK).unwrap();
+    });
+
+    assert_eq!(LOG.wait(5), [
+        "high waits",
+        "low unlocks",
+        "high locked 42",
+        "mid runs",
+        "low done",
+    ]);
+}
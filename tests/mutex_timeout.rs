@@ -0,0 +1,91 @@
+//! RusTOS - Rust Real Time Operating System 
+//! Copyright (C) 2025 - Fabio Zanin - fabio.zanin93@outlook.com
+//! 
+//! This program is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License.
+//! 
+//! This program is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+//! 
+//! You should have received a copy of the GNU General Public License
+//! along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+mod common;
+
+use std::cell::Cell;
+
+use common::{now, start, Log};
+use RusTOS::kernel::*;
+
+static OWNER_STACK: Stack::<256> = Stack::new();
+static CONTENDER_STACK: Stack::<256> = Stack::new();
+
+static OWNER_TASK: Task = Task::new(owner, 2, &OWNER_STACK);
+static CONTENDER_TASK: Task = Task::new(contender, 1, &CONTENDER_STACK);
+
+static MUTEX: Mutex<u32> = Mutex::new(0);
+static RECURSIVE: RecursiveMutex<Cell<u32>> = RecursiveMutex::new(Cell::new(0));
+static LOG: Log = Log::new();
+
+fn owner(task: &mut Task) -> ! {
+    let mut guard = MUTEX.lock(task).unwrap();
+    LOG.push(format!("owner relock {:?}", MUTEX.try_lock(task).err()));
+    task.sleep(30);
+    *guard = 7;
+    drop(guard);
+
+    // Nested locks: the RecursiveMutex is released with the outer guard only
+    let outer = RECURSIVE.lock(task).unwrap();
+    let inner = RECURSIVE.lock(task).unwrap();
+    inner.set(inner.get() + 1);
+    drop(inner);
+    task.sleep(10);
+    outer.set(outer.get() + 1);
+    drop(outer);
+
+    loop {
+        task.sleep(1000);
+    }
+}
+
+fn contender(task: &mut Task) -> ! {
+    LOG.push(format!("try {:?}", MUTEX.try_lock(task).err()));
+
+    let start = now();
+    let res = MUTEX.lock_timeout(task, ms::new(10)).err();
+    LOG.push(format!("timeout {:?} {}", res, now() - start >= 10));
+
+    let value = MUTEX.lock_timeout(task, ms::new(100)).map(|guard| *guard);
+    LOG.push(format!("handed over {:?}", value));
+
+    // Owner still holds the outer guard
+    LOG.push(format!("recursive try {:?}", RECURSIVE.try_lock(task).err()));
+    let value = RECURSIVE.lock_timeout(task, ms::new(100)).map(|guard| guard.get());
+    LOG.push(format!("recursive released {:?}", value));
+
+    loop {
+        task.sleep(1000);
+    }
+}
+
+/// A locked Mutex makes try_lock fail at once and lock_timeout after the timeout, then the Mutex is handed
+/// over on unlock; a RecursiveMutex can be locked again by its owner and is released with the last guard.
+#[test]
+fn mutex_try_timeout_and_recursive() {
+    start(|k| {
+        k.add_task(&CONTENDER_TASK).unwrap();
+        k.add_task(&OWNER_TASK).unwrap();
+    });
+
+    assert_eq!(LOG.wait(6), [
+        "owner relock Some(Recursive)",
+        "try Some(WouldBlock)",
+        "timeout Some(Timeout) true",
+        "handed over Ok(7)",
+        "recursive try Some(WouldBlock)",
+        "recursive released Ok(2)",
+    ]);
+}
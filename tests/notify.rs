@@ -0,0 +1,83 @@
+//! RusTOS - Rust Real Time Operating System 
+//! Copyright (C) 2025 - Fabio Zanin - fabio.zanin93@outlook.com
+//! 
+//! This program is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License.
+//! 
+//! This program is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+//! 
+//! You should have received a copy of the GNU General Public License
+//! along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+mod common;
+
+use common::{now, start, Log};
+use RusTOS::kernel::*;
+
+static WAITER_STACK: Stack::<256> = Stack::new();
+static NOTIFIER_STACK: Stack::<256> = Stack::new();
+
+static WAITER_TASK: Task = Task::new(waiter, 2, &WAITER_STACK);
+static NOTIFIER_TASK: Task = Task::new(notifier, 1, &NOTIFIER_STACK);
+
+static LOG: Log = Log::new();
+
+fn waiter(task: &mut Task) -> ! {
+    let start = now();
+    let res = task.wait_notification(0, ms::new(10));
+    LOG.push(format!("timeout {:?} {}", res, now() - start >= 10));
+
+    // Notifier runs while this Task sleeps, then every wait gets the value left by its actions
+    task.sleep(20);
+    LOG.push(format!("set {:?}", task.wait_notification(0, ms::new(10))));
+    task.sleep(20);
+    LOG.push(format!("bits {:?}", task.wait_notification(0b0011, ms::new(10))));
+    task.sleep(20);
+    LOG.push(format!("increment {:?}", task.wait_notification(usize::MAX, ms::new(10))));
+
+    loop {
+        task.sleep(1000);
+    }
+}
+
+fn notifier(task: &mut Task) -> ! {
+    task.sleep(15);
+    let first = notify(&WAITER_TASK, 1, NotifyAction::Set);
+    let pending = notify(&WAITER_TASK, 2, NotifyAction::Set);
+    let _ = notify(&WAITER_TASK, 3, NotifyAction::Overwrite);
+    LOG.push(format!("pending {:?} {:?}", first, pending));
+
+    // Value is kept after a wait with an empty clear mask
+    task.sleep(20);
+    let _ = notify(&WAITER_TASK, 0b1100, NotifyAction::SetBits);
+
+    task.sleep(20);
+    let _ = notify(&WAITER_TASK, 0, NotifyAction::Increment);
+    let _ = notify(&WAITER_TASK, 0, NotifyAction::Increment);
+
+    loop {
+        task.sleep(1000);
+    }
+}
+
+/// Set fails while a notification is pending, Overwrite doesn't, SetBits merges bits into the value
+/// left by the previous wait, Increment counts notifications; a wait with nothing pending times out.
+#[test]
+fn notify_actions_update_value() {
+    start(|k| {
+        k.add_task(&NOTIFIER_TASK).unwrap();
+        k.add_task(&WAITER_TASK).unwrap();
+    });
+
+    assert_eq!(LOG.wait(5), [
+        "timeout Err(Timeout) true",
+        "pending Ok(()) Err(Pending)",
+        "set Ok(3)",
+        "bits Ok(15)",
+        "increment Ok(14)",
+    ]);
+}
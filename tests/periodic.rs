@@ -0,0 +1,87 @@
+//! RusTOS - Rust Real Time Operating System 
+//! Copyright (C) 2025 - Fabio Zanin - fabio.zanin93@outlook.com
+//! 
+//! This program is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License.
+//! 
+//! This program is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+//! 
+//! You should have received a copy of the GNU General Public License
+//! along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+mod common;
+
+use common::{now, start, Log};
+use RusTOS::kernel::*;
+
+static PERIODIC_STACK: Stack::<256> = Stack::new();
+static UNTIL_STACK: Stack::<256> = Stack::new();
+
+static PERIODIC_TASK: Task = Task::new(periodic, 2, &PERIODIC_STACK);
+static UNTIL_TASK: Task = Task::new(until, 1, &UNTIL_STACK);
+
+static LOG: Log = Log::new();
+
+/// Keeps the core busy till the tick, reading the time lets the Task be preempted meanwhile
+fn spin_until(tick: SystemTicks) {
+    while now() < tick {}
+}
+
+fn periodic(task: &mut Task) -> ! {
+    let mut period = Periodic::new(Duration::new(10));
+    let base = period.next().ticks() - 10;
+
+    let wakes: Vec<SystemTicks> = (0..3).map(|_| {
+        let _ = period.wait(task);
+        now() - base
+    }).collect();
+    LOG.push(format!("cadence {:?}", wakes));
+
+    // Busy past the deadline at 40: the period is overrun and the one at 50 is skipped
+    spin_until(base + 55);
+    let res = period.wait(task).map_err(|delay| delay.ticks() >= 15);
+    LOG.push(format!("overrun {:?} {} next {}", res, period.overruns(), period.next().ticks() - base));
+
+    let _ = period.wait(task);
+    LOG.push(format!("back on cadence {}", now() - base));
+
+    loop {
+        task.sleep(1000);
+    }
+}
+
+fn until(task: &mut Task) -> ! {
+    // Woken up at 35, but the periodic Task keeps the core till 55: it is not an overrun
+    let wake = Instant::from_ticks(now() + 35);
+    let res = task.sleep_until(wake).map_err(|delay| delay.ticks());
+    LOG.push(format!("until {:?} late {}", res, now() > wake.ticks()));
+
+    let res = task.sleep_until(wake).map_err(|delay| delay.ticks() >= 15);
+    LOG.push(format!("past {:?}", res));
+
+    loop {
+        task.sleep(1000);
+    }
+}
+
+/// Periodic wakes up at multiples of its period, skipping the periods it overruns; sleep_until fails only
+/// for an instant already passed, not when the Task is delayed after sleeping.
+#[test]
+fn periodic_keeps_cadence() {
+    start(|k| {
+        k.add_task(&UNTIL_TASK).unwrap();
+        k.add_task(&PERIODIC_TASK).unwrap();
+    });
+
+    assert_eq!(LOG.wait(5), [
+        "cadence [10, 20, 30]",
+        "overrun Err(true) 1 next 60",
+        "until Ok(()) late true",
+        "past Err(true)",
+        "back on cadence 60",
+    ]);
+}
@@ -0,0 +1,74 @@
+//! RusTOS - Rust Real Time Operating System 
+//! Copyright (C) 2025 - Fabio Zanin - fabio.zanin93@outlook.com
+//! 
+//! This program is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License.
+//! 
+//! This program is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+//! 
+//! You should have received a copy of the GNU General Public License
+//! along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+mod common;
+
+use common::{now, start, Log};
+use RusTOS::kernel::*;
+
+static PRODUCER_STACK: Stack::<256> = Stack::new();
+static CONSUMER_STACK: Stack::<256> = Stack::new();
+
+static PRODUCER_TASK: Task = Task::new(producer, 1, &PRODUCER_STACK);
+static CONSUMER_TASK: Task = Task::new(consumer, 2, &CONSUMER_STACK);
+
+static QUEUE: Queue<u32, 4> = Queue::new();
+static LOG: Log = Log::new();
+
+fn producer(task: &mut Task) -> ! {
+    for value in 0..10 {
+        QUEUE.push(task, value);
+    }
+    LOG.push("produced");
+
+    loop {
+        task.sleep(1000);
+    }
+}
+
+fn consumer(task: &mut Task) -> ! {
+    // Producer fills the Queue and blocks meanwhile
+    task.sleep(10);
+    LOG.push(format!("count {}", QUEUE.count()));
+    LOG.push(format!("dropping {:?}", QUEUE.push_dropping(99)));
+
+    let values: Vec<u32> = (0..10).map(|_| QUEUE.pop(task)).collect();
+    LOG.push(format!("{:?}", values));
+
+    let start = now();
+    let res = QUEUE.pop_timeout(task, ms::new(10));
+    LOG.push(format!("timeout {:?} {}", res, now() - start >= 10));
+
+    loop {
+        task.sleep(1000);
+    }
+}
+
+/// Elements come out in order, a full Queue blocks the producer and an empty one times out
+#[test]
+fn queue_blocks_and_keeps_order() {
+    start(|k| {
+        k.add_task(&PRODUCER_TASK).unwrap();
+        k.add_task(&CONSUMER_TASK).unwrap();
+    });
+
+    let log = LOG.wait(5);
+    assert_eq!(log[0], "count 4");
+    assert_eq!(log[1], "dropping Err(())");
+    // Producer ends once the consumer has made room for its last element
+    assert!(log[2..4].contains(&"[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]".to_string()));
+    assert!(log[2..4].contains(&"produced".to_string()));
+    assert_eq!(log[4], "timeout Err(()) true");
+}
@@ -0,0 +1,62 @@
+//! RusTOS - Rust Real Time Operating System 
+//! Copyright (C) 2025 - Fabio Zanin - fabio.zanin93@outlook.com
+//! 
+//! This program is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License.
+//! 
+//! This program is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+//! 
+//! You should have received a copy of the GNU General Public License
+//! along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+mod common;
+
+use common::{now, start, Log};
+use RusTOS::kernel::*;
+
+static FAST_STACK: Stack::<256> = Stack::new();
+static SLOW_STACK: Stack::<256> = Stack::new();
+
+static FAST_TASK: Task = Task::new(fast, 2, &FAST_STACK);
+static SLOW_TASK: Task = Task::new(slow, 2, &SLOW_STACK);
+
+static MEETING: Rendezvous = Rendezvous::new(&[&FAST_TASK, &SLOW_TASK]);
+
+static LOG: Log = Log::new();
+
+fn fast(task: &mut Task) -> ! {
+    let start = now();
+    MEETING.meet(task);
+    LOG.push(format!("fast met after slow {}", now() - start >= 30));
+
+    loop {
+        task.sleep(1000);
+    }
+}
+
+fn slow(task: &mut Task) -> ! {
+    task.sleep(30);
+    MEETING.meet(task);
+    LOG.push("slow met");
+
+    loop {
+        task.sleep(1000);
+    }
+}
+
+/// Tasks of the same priority are listed by reference: the first one waits till the last one arrives.
+#[test]
+fn tasks_meet_at_rendezvous() {
+    start(|k| {
+        k.add_task(&FAST_TASK).unwrap();
+        k.add_task(&SLOW_TASK).unwrap();
+    });
+
+    let mut log = LOG.wait(2);
+    log.sort();
+    assert_eq!(log, ["fast met after slow true", "slow met"]);
+}
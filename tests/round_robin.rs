@@ -0,0 +1,66 @@
+//! RusTOS - Rust Real Time Operating System 
+//! Copyright (C) 2025 - Fabio Zanin - fabio.zanin93@outlook.com
+//! 
+//! This program is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License.
+//! 
+//! This program is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+//! 
+//! You should have received a copy of the GNU General Public License
+//! along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use common::{now, start, Log};
+use RusTOS::kernel::*;
+
+static A_STACK: Stack::<256> = Stack::new();
+static B_STACK: Stack::<256> = Stack::new();
+static C_STACK: Stack::<256> = Stack::new();
+
+static A_TASK: Task = Task::new(worker, 1, &A_STACK);
+static B_TASK: Task = Task::new(worker, 1, &B_STACK);
+static C_TASK: Task = Task::new(worker, 1, &C_STACK);
+
+/// Slot of the Task that has run last
+static LAST: AtomicUsize = AtomicUsize::new(usize::MAX);
+static LOG: Log = Log::new();
+
+/// Never blocks: it records its slot whenever it gets the core back, till tick 60
+fn worker(task: &mut Task) -> ! {
+    while now() < 60 {
+        let me = task.id();
+        if LAST.swap(me, Ordering::Relaxed) != me {
+            LOG.push(me.to_string());
+        }
+    }
+
+    loop {
+        task.sleep(1000);
+    }
+}
+
+/// Tasks of the same priority that never block share the core, each one for a time slice in turn
+#[test]
+fn same_priority_tasks_take_turns() {
+    start(|k| {
+        k.set_time_slice(5);
+        k.add_task(&A_TASK).unwrap();
+        k.add_task(&B_TASK).unwrap();
+        k.add_task(&C_TASK).unwrap();
+    });
+
+    let log = LOG.wait(9);
+    for turn in log[..9].windows(3) {
+        let mut slots = turn.to_vec();
+        slots.sort();
+        slots.dedup();
+        assert_eq!(slots.len(), 3, "not a round-robin: {:?}", log);
+    }
+}
@@ -0,0 +1,67 @@
+//! RusTOS - Rust Real Time Operating System 
+//! Copyright (C) 2025 - Fabio Zanin - fabio.zanin93@outlook.com
+//! 
+//! This program is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License.
+//! 
+//! This program is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+//! 
+//! You should have received a copy of the GNU General Public License
+//! along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use common::{now, start, Log};
+use RusTOS::kernel::*;
+
+static WAITER_STACK: Stack::<256> = Stack::new();
+static WAITER_TASK: Task = Task::new(waiter, 1, &WAITER_STACK);
+
+static SEMAPHORE: Semaphore = Semaphore::new();
+static TIMER: AtomicUsize = AtomicUsize::new(0);
+static LOG: Log = Log::new();
+
+/// Timers fire in SysTick, thus they use the ISR API
+fn release() {
+    let mut woken = false;
+    SEMAPHORE.release_from_isr(&mut woken);
+}
+
+fn waiter(task: &mut Task) -> ! {
+    let start = now();
+    let released = (0..5).filter(|_| SEMAPHORE.wait(task, ms::new(50)).is_ok()).count();
+    // Five periods, less the ticks gone before the Task started
+    LOG.push(format!("released {} on time {}", released, now() - start >= 40));
+
+    let cs = CritSect::activate();
+    let _ = KERNEL.access(&cs).remove_timer(TIMER.load(Ordering::Relaxed));
+    cs.deactivate();
+
+    LOG.push(format!("after removal {:?}", SEMAPHORE.wait(task, ms::new(30))));
+
+    loop {
+        task.sleep(1000);
+    }
+}
+
+/// A looping Timer wakes up a Task waiting on a Semaphore at every period,
+/// then the wait times out once the Timer is gone.
+#[test]
+fn timer_releases_semaphore() {
+    start(|k| {
+        k.add_task(&WAITER_TASK).unwrap();
+        let timer = k.new_timer(Duration::new(10), release, true, TimerMode::Looping).unwrap();
+        TIMER.store(timer.id(), Ordering::Relaxed);
+    });
+
+    assert_eq!(LOG.wait(2), [
+        "released 5 on time true",
+        "after removal Err(())",
+    ]);
+}
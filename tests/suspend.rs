@@ -0,0 +1,80 @@
+//! RusTOS - Rust Real Time Operating System 
+//! Copyright (C) 2025 - Fabio Zanin - fabio.zanin93@outlook.com
+//! 
+//! This program is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License.
+//! 
+//! This program is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+//! 
+//! You should have received a copy of the GNU General Public License
+//! along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+mod common;
+
+use common::{start, Log};
+use RusTOS::kernel::*;
+
+static BLOCKED_STACK: Stack::<256> = Stack::new();
+static CONTROLLER_STACK: Stack::<256> = Stack::new();
+
+static BLOCKED_TASK: Task = Task::new(blocked, 3, &BLOCKED_STACK);
+static CONTROLLER_TASK: Task = Task::new(controller, 1, &CONTROLLER_STACK);
+
+static SEMAPHORE: Semaphore = Semaphore::new();
+static LOG: Log = Log::new();
+
+fn blocked(task: &mut Task) -> ! {
+    SEMAPHORE.acquire(task);
+    LOG.push("blocked woken");
+
+    let _ = suspend(task);
+    LOG.push("blocked resumed");
+
+    loop {
+        task.sleep(1000);
+    }
+}
+
+fn controller(task: &mut Task) -> ! {
+    task.sleep(5);
+    LOG.push(format!("suspend {:?} {}", suspend(&BLOCKED_TASK), is_suspended(&BLOCKED_TASK)));
+
+    // The suspended Task gets the Semaphore, but it doesn't run till resumed
+    SEMAPHORE.release();
+    task.sleep(10);
+    LOG.push("released");
+
+    // Once resumed, the Task suspends itself
+    LOG.push(format!("resume {:?}", resume(&BLOCKED_TASK)));
+    LOG.push(format!("self suspended {}", is_suspended(&BLOCKED_TASK)));
+    let _ = resume(&BLOCKED_TASK);
+    LOG.push(format!("again {:?}", resume(&BLOCKED_TASK)));
+
+    loop {
+        task.sleep(1000);
+    }
+}
+
+/// A Task suspended while blocked is woken up by the object it waits for but runs only once resumed;
+/// a Task can suspend itself, and resuming a Task that is not suspended fails.
+#[test]
+fn suspended_task_runs_once_resumed() {
+    start(|k| {
+        k.add_task(&CONTROLLER_TASK).unwrap();
+        k.add_task(&BLOCKED_TASK).unwrap();
+    });
+
+    assert_eq!(LOG.wait(7), [
+        "suspend Ok(()) true",
+        "released",
+        "blocked woken",
+        "resume Ok(())",
+        "self suspended true",
+        "blocked resumed",
+        "again Err(NotSuspended)",
+    ]);
+}
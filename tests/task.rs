@@ -0,0 +1,72 @@
+//! RusTOS - Rust Real Time Operating System 
+//! Copyright (C) 2025 - Fabio Zanin - fabio.zanin93@outlook.com
+//! 
+//! This program is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License.
+//! 
+//! This program is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+//! 
+//! You should have received a copy of the GNU General Public License
+//! along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+mod common;
+
+use common::{now, start, Log};
+use RusTOS::kernel::*;
+
+static HIGH_STACK: Stack::<256> = Stack::new();
+static LOW_STACK: Stack::<256> = Stack::new();
+
+static HIGH_TASK: Task = Task::new(high, 2, &HIGH_STACK);
+static LOW_TASK: Task = Task::new(low, 1, &LOW_STACK);
+
+static LOG: Log = Log::new();
+
+fn high(task: &mut Task) -> ! {
+    LOG.push("high started");
+
+    let start = now();
+    task.sleep(20);
+    LOG.push(format!("high slept {}", now() - start >= 20));
+
+    let value = task.wait_notification(usize::MAX, ms::new(0));
+    LOG.push(format!("high notified {:?}", value));
+
+    loop {
+        task.sleep(1000);
+    }
+}
+
+fn low(task: &mut Task) -> ! {
+    LOG.push("low started");
+
+    task.sleep(40);
+    let _ = notify(&HIGH_TASK, 42, NotifyAction::Set);
+    LOG.push("low after notify");
+
+    loop {
+        task.sleep(1000);
+    }
+}
+
+/// The highest priority Task runs first, lower ones run while it sleeps,
+/// and a notification preempts the notifier in favour of the waiting Task.
+#[test]
+fn tasks_run_by_priority() {
+    start(|k| {
+        k.add_task(&LOW_TASK).unwrap();
+        k.add_task(&HIGH_TASK).unwrap();
+    });
+
+    assert_eq!(LOG.wait(5), [
+        "high started",
+        "low started",
+        "high slept true",
+        "high notified Ok(42)",
+        "low after notify",
+    ]);
+}
@@ -0,0 +1,68 @@
+//! RusTOS - Rust Real Time Operating System 
+//! Copyright (C) 2025 - Fabio Zanin - fabio.zanin93@outlook.com
+//! 
+//! This program is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License.
+//! 
+//! This program is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+//! 
+//! You should have received a copy of the GNU General Public License
+//! along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use common::{start, Log};
+use RusTOS::kernel::*;
+
+static MANAGER_STACK: Stack::<256> = Stack::new();
+static MANAGER_TASK: Task = Task::new(manager, 1, &MANAGER_STACK);
+
+static POOL: TaskPool<2, 256> = TaskPool::new();
+static SPAWNED: AtomicUsize = AtomicUsize::new(0);
+static LOG: Log = Log::new();
+
+fn child(task: &mut Task) -> ! {
+    LOG.push(format!("child {}", SPAWNED.fetch_add(1, Ordering::Relaxed)));
+
+    loop {
+        task.sleep(1000);
+    }
+}
+
+fn manager(task: &mut Task) -> ! {
+    // Children have an higher priority, so they run as soon as they are spawned
+    let first = POOL.spawn(child, 2).unwrap();
+    let _second = POOL.spawn(child, 2).unwrap();
+    LOG.push(format!("full {:?}", POOL.spawn(child, 2).err()));
+
+    LOG.push(format!("delete {:?} {:?}", POOL.delete(first), POOL.delete(first)));
+    let third = POOL.spawn(child, 2).unwrap();
+    LOG.push(format!("reused {}", core::ptr::eq(first, third)));
+
+    loop {
+        task.sleep(1000);
+    }
+}
+
+/// A pool spawns Tasks up to its size, a deleted Task can't be deleted again and its slot is reused
+#[test]
+fn pool_spawns_and_reuses_slots() {
+    start(|k| {
+        k.add_task(&MANAGER_TASK).unwrap();
+    });
+
+    assert_eq!(LOG.wait(6), [
+        "child 0",
+        "child 1",
+        "full Some(Full)",
+        "delete Ok(()) Err(NotInPool)",
+        "child 2",
+        "reused true",
+    ]);
+}
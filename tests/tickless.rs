@@ -0,0 +1,71 @@
+//! RusTOS - Rust Real Time Operating System 
+//! Copyright (C) 2025 - Fabio Zanin - fabio.zanin93@outlook.com
+//! 
+//! This program is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License.
+//! 
+//! This program is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+//! 
+//! You should have received a copy of the GNU General Public License
+//! along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Needs the tickless feature: `cargo test --target x86_64-unknown-linux-gnu --features tickless`
+
+#![cfg(feature = "tickless")]
+
+mod common;
+
+use common::{now, start, Log};
+use RusTOS::kernel::*;
+
+static SHORT_STACK: Stack::<256> = Stack::new();
+static LONG_STACK: Stack::<256> = Stack::new();
+
+static SHORT_TASK: Task = Task::new(short, 2, &SHORT_STACK);
+static LONG_TASK: Task = Task::new(long, 1, &LONG_STACK);
+
+static LOG: Log = Log::new();
+
+fn short(task: &mut Task) -> ! {
+    for _ in 0..3 {
+        let start = now();
+        task.sleep(7);
+        let slept = now() - start;
+        LOG.push(format!("short {}", (7..=8).contains(&slept)));
+    }
+
+    loop {
+        task.sleep(1000);
+    }
+}
+
+fn long(task: &mut Task) -> ! {
+    let start = now();
+    task.sleep(30);
+    let slept = now() - start;
+    LOG.push(format!("long {}", (30..=31).contains(&slept)));
+
+    loop {
+        task.sleep(1000);
+    }
+}
+
+/// With all Tasks asleep the idle Task suppresses ticks, still every Task wakes up on its own tick
+#[test]
+fn sleeps_end_on_time_while_idle() {
+    start(|k| {
+        k.add_task(&LONG_TASK).unwrap();
+        k.add_task(&SHORT_TASK).unwrap();
+    });
+
+    assert_eq!(LOG.wait(4), [
+        "short true",
+        "short true",
+        "short true",
+        "long true",
+    ]);
+}
@@ -0,0 +1,95 @@
+//! RusTOS - Rust Real Time Operating System 
+//! Copyright (C) 2025 - Fabio Zanin - fabio.zanin93@outlook.com
+//! 
+//! This program is free software: you can redistribute it and/or modify
+//! it under the terms of the GNU General Public License as published by
+//! the Free Software Foundation, either version 3 of the License.
+//! 
+//! This program is distributed in the hope that it will be useful,
+//! but WITHOUT ANY WARRANTY; without even the implied warranty of
+//! MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//! GNU General Public License for more details.
+//! 
+//! You should have received a copy of the GNU General Public License
+//! along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Needs the timer_service feature: `cargo test --target x86_64-unknown-linux-gnu --features timer_service`
+
+#![cfg(feature = "timer_service")]
+
+mod common;
+
+use std::sync::atomic::{AtomicPtr, Ordering};
+use std::sync::Mutex;
+
+use common::{now, start, Log};
+use RusTOS::kernel::*;
+
+static TIMERS_STACK: Stack::<256> = Stack::new();
+static CONTROLLER_STACK: Stack::<256> = Stack::new();
+
+static TIMERS_TASK: Task = Task::new(timer_service, 3, &TIMERS_STACK);
+static CONTROLLER_TASK: Task = Task::new(controller, 1, &CONTROLLER_STACK);
+
+/// Ticks the Timer has fired at, the context of its callback
+static FIRES: Mutex<Vec<SystemTicks>> = Mutex::new(Vec::new());
+/// Timers live in the Kernel static
+static TIMER: AtomicPtr<Timer> = AtomicPtr::new(core::ptr::null_mut());
+static LOG: Log = Log::new();
+
+fn fire(fires: &'static Mutex<Vec<SystemTicks>>) {
+    fires.lock().unwrap().push(now());
+}
+
+fn fired() -> Vec<SystemTicks> {
+    FIRES.lock().unwrap().clone()
+}
+
+/// Ticks between consecutive fires
+fn periods(fires: &[SystemTicks]) -> Vec<SystemTicks> {
+    fires.windows(2).map(|pair| pair[1] - pair[0]).collect()
+}
+
+fn controller(task: &mut Task) -> ! {
+    let timer = unsafe { &*TIMER.load(Ordering::Relaxed) };
+
+    task.sleep(35);
+    LOG.push(format!("stop {:?}", timer.command(task, TimerCommand::Stop, ms::new(10))));
+    let stopped = fired();
+    task.sleep(30);
+    LOG.push(format!("looping {:?} stopped {}", periods(&stopped), fired().len() == stopped.len()));
+
+    LOG.push(format!("change {:?}", timer.command(task, TimerCommand::ChangePeriod(Duration::new(5)), ms::new(10))));
+    task.sleep(22);
+    let _ = timer.command(task, TimerCommand::Stop, ms::new(10));
+    LOG.push(format!("changed {:?}", periods(&fired()[stopped.len()..])));
+
+    let cs = CritSect::activate();
+    let res = KERNEL.access(&cs).add_timer_service(&CONTROLLER_TASK);
+    cs.deactivate();
+    LOG.push(format!("service again {:?}", res));
+
+    loop {
+        task.sleep(1000);
+    }
+}
+
+/// Callbacks are called by the service Task at their period, with their context, and Timers follow
+/// the commands sent by Tasks; there can be only one service Task.
+#[test]
+fn timer_service_runs_callbacks_and_commands() {
+    start(|k| {
+        k.add_timer_service(&TIMERS_TASK).unwrap();
+        k.add_task(&CONTROLLER_TASK).unwrap();
+        let timer = k.new_timer_with(Duration::new(10), TimerCallback::with_context(fire, &FIRES), true, TimerMode::Looping).unwrap();
+        TIMER.store(timer as *const Timer as *mut Timer, Ordering::Relaxed);
+    });
+
+    assert_eq!(LOG.wait(5), [
+        "stop Ok(())",
+        "looping [10, 10] stopped true",
+        "change Ok(())",
+        "changed [5, 5, 5]",
+        "service again Err(ServiceAdded)",
+    ]);
+}